@@ -1,26 +1,28 @@
 extern crate ctrlc;
-use std::process;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
 fn main() {
-    let running = Arc::new(AtomicUsize::new(0));
-    let r = running.clone();
+    // The second Ctrl-C exits right away.
+    ctrlc::set_policy(ctrlc::Policy::ForceExitAfter {
+        count: 2,
+        code: 0,
+        window: None,
+    });
+
+    let exiting = Arc::new(AtomicBool::new(false));
+    let e = exiting.clone();
     ctrlc::set_handler(move || {
-        let prev = r.fetch_add(1, Ordering::SeqCst);
-        if prev == 0 {
-            println!("Exiting...");
-        } else {
-            process::exit(0);
-        }
+        println!("Exiting...");
+        e.store(true, Ordering::SeqCst);
     })
     .expect("Error setting Ctrl-C handler");
     println!("Running...");
     for _ in 1..6 {
         thread::sleep(Duration::from_secs(5));
-        if running.load(Ordering::SeqCst) > 0 {
+        if exiting.load(Ordering::SeqCst) {
             break;
         }
     }
@@ -0,0 +1,129 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use error::Error;
+use handler::{self, Config, Handler};
+use policy::{self, Policy};
+use std::mem;
+use SignalType;
+
+/// Configure a signal handler before registering it.
+///
+/// The defaults match [set_handler()](fn.set_handler.html): `Ctrl+C` (and `SIGTERM` with
+/// the `termination` feature), a thread named `"ctrl-c"` with the default stack size and
+/// `SA_RESTART`. Unlike the `termination` feature, the signals chosen here only affect the
+/// handler being built, so a library can handle `SIGTERM` without enabling it for every crate
+/// in the dependency graph.
+///
+/// # Example
+/// ```no_run
+/// use ctrlc::{Policy, SignalType};
+///
+/// ctrlc::Builder::new()
+///     .signals(&[SignalType::Ctrlc, SignalType::Termination])
+///     .thread_name("shutdown")
+///     .policy(Policy::ExitBySignal)
+///     .install(|signal| println!("Received {:?}, shutting down", signal))
+///     .expect("Error setting Ctrl-C handler");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    config: Config,
+    policy: Option<Policy>,
+}
+
+impl Builder {
+    /// Create a builder with the default configuration.
+    pub fn new() -> Builder {
+        Builder::default()
+    }
+
+    /// Set the signals the handler is executed for.
+    pub fn signals(mut self, signals: &[SignalType]) -> Builder {
+        self.config.signals = signals.to_vec();
+        self
+    }
+
+    /// Set the name of the signal handling thread.
+    pub fn thread_name<S: Into<String>>(mut self, name: S) -> Builder {
+        self.config.thread_name = name.into();
+        self
+    }
+
+    /// Set the stack size of the signal handling thread, in bytes.
+    pub fn stack_size(mut self, size: usize) -> Builder {
+        self.config.stack_size = Some(size);
+        self
+    }
+
+    /// Set whether system calls interrupted by the signals are restarted (`SA_RESTART`).
+    ///
+    /// Without it, blocking calls on other threads fail with `EINTR` when a signal arrives,
+    /// which some programs use to cancel them. Has no effect on Windows and with the
+    /// `signalfd` backend.
+    pub fn sa_restart(mut self, restart: bool) -> Builder {
+        self.config.sa_restart = restart;
+        self
+    }
+
+    /// Set the [Policy](enum.Policy.html) of the signal handling thread, like
+    /// [set_policy()](fn.set_policy.html) does. It is set before the handler is registered,
+    /// so that it applies to the first signal, and the previous policy is restored if
+    /// registering fails.
+    pub fn policy(mut self, policy: Policy) -> Builder {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Register the signal handler.
+    ///
+    /// Like [set_handler()](fn.set_handler.html), the handler stays registered for the rest of
+    /// the program.
+    ///
+    /// # Errors
+    /// Will return [Error::NoSuchSignal](enum.Error.html#variant.NoSuchSignal) if one of the
+    /// signals can't be handled on this platform, an error if another `ctrlc::set_handler()`
+    /// handler exists or if a system error occurred while setting the handler.
+    ///
+    pub fn install<F>(self, user_handler: F) -> Result<(), Error>
+    where
+        F: FnMut(SignalType) + 'static + Send,
+    {
+        self.install_handler(user_handler).map(mem::forget)
+    }
+
+    /// Register the signal handler and return a [Handler](struct.Handler.html) that removes
+    /// it again when dropped.
+    ///
+    /// # Errors
+    /// Will return [Error::NoSuchSignal](enum.Error.html#variant.NoSuchSignal) if one of the
+    /// signals can't be handled on this platform, an error if another `ctrlc::set_handler()`
+    /// handler exists or if a system error occurred while setting the handler.
+    ///
+    /// If [subscribers](fn.subscribe.html) already started the signal handling thread, the
+    /// handler is only added to it if the configuration matches that of the thread, and
+    /// [Error::MultipleHandlers](enum.Error.html#variant.MultipleHandlers) is returned
+    /// otherwise.
+    ///
+    pub fn install_handler<F>(self, user_handler: F) -> Result<Handler, Error>
+    where
+        F: FnMut(SignalType) + 'static + Send,
+    {
+        let old_policy = self.policy.map(|policy| {
+            let old_policy = policy::policy();
+            policy::set_policy(policy);
+            old_policy
+        });
+        handler::install_configured(&self.config, user_handler).inspect_err(|_| {
+            if let Some(old_policy) = old_policy {
+                policy::set_policy(old_policy);
+            }
+        })
+    }
+}
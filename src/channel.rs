@@ -0,0 +1,56 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+#[cfg(feature = "crossbeam-channel")]
+extern crate crossbeam_channel as crossbeam;
+
+use error::Error;
+use std::sync::mpsc;
+use subscriber::subscribe_while;
+use SignalType;
+
+/// Create a channel that receives every Ctrl-C signal.
+///
+/// The receiver is registered as a [subscriber](fn.subscribe.html), so it works next to a
+/// handler set with [set_handler()](fn.set_handler.html) or other channels. Each receiver
+/// gets every signal. The subscriber is removed on the first signal after the receiver was
+/// dropped.
+///
+/// # Example
+/// ```no_run
+/// let signals = ctrlc::channel().expect("Error setting Ctrl-C handler");
+///
+/// println!("Waiting for Ctrl-C...");
+/// signals.recv().expect("Could not receive from channel.");
+/// println!("Got it! Exiting...");
+/// ```
+///
+/// # Errors
+/// Will return an error if a system error occurred while setting the handler.
+///
+pub fn channel() -> Result<mpsc::Receiver<SignalType>, Error> {
+    let (tx, rx) = mpsc::channel();
+    subscribe_while(move |signal| tx.send(signal).is_ok())?;
+    Ok(rx)
+}
+
+/// Create a [crossbeam-channel](https://docs.rs/crossbeam-channel) that receives every
+/// Ctrl-C signal, for use in `select!` loops.
+///
+/// Works like [channel()](fn.channel.html). Requires the `crossbeam-channel` feature.
+///
+/// # Errors
+/// Will return an error if a system error occurred while setting the handler.
+///
+#[cfg(feature = "crossbeam-channel")]
+pub fn crossbeam_channel() -> Result<crossbeam::Receiver<SignalType>, Error> {
+    let (tx, rx) = crossbeam::unbounded();
+    subscribe_while(move |signal| tx.send(signal).is_ok())?;
+    Ok(rx)
+}
@@ -0,0 +1,117 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use platform;
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::Mutex;
+use SignalInfo;
+
+struct State {
+    /// Number of live guards.
+    depth: usize,
+    /// Signals received while deferred, oldest first.
+    queue: VecDeque<SignalInfo>,
+    /// Number of wake-ups of the signal handling thread that ask it to deliver the queue.
+    flush: usize,
+    /// Whether the signal handling thread is running, so that it can be woken up.
+    running: bool,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    depth: 0,
+    queue: VecDeque::new(),
+    flush: 0,
+    running: false,
+});
+
+/// Holds off handling signals until dropped, see [defer()](fn.defer.html).
+#[must_use = "signals are only deferred while the guard is alive"]
+#[derive(Debug)]
+pub struct DeferGuard {
+    _private: (),
+}
+
+/// Defer handling signals until the returned guard is dropped, e.g. while a transaction or
+/// a file rename is in progress.
+///
+/// Signals received in the meantime are queued by the dedicated signal handling thread, and
+/// handled in the order they were received once the last guard is dropped. Guards can be
+/// nested and held on several threads at once.
+///
+/// Only affects the handler closure, [subscribers](fn.subscribe.html) and
+/// [Policy::ExitBySignal](enum.Policy.html#variant.ExitBySignal), which runs after them, not
+/// signals taken with [try_recv()](fn.try_recv.html) or the async runtime integrations.
+/// [Policy::ForceExitAfter](enum.Policy.html#variant.ForceExitAfter) counts the signals as
+/// they arrive, so that it still ends a program stuck in a deferred section.
+///
+/// # Example
+/// ```no_run
+/// ctrlc::set_handler(|| std::process::exit(130)).expect("Error setting Ctrl-C handler");
+///
+/// {
+///     let _guard = ctrlc::defer();
+///     std::fs::rename("config.toml.new", "config.toml").unwrap();
+/// }
+/// // A Ctrl-C received during the rename exits the process here.
+/// ```
+///
+pub fn defer() -> DeferGuard {
+    STATE.lock().unwrap().depth += 1;
+    DeferGuard { _private: () }
+}
+
+impl Drop for DeferGuard {
+    fn drop(&mut self) {
+        let mut state = STATE.lock().unwrap();
+        state.depth -= 1;
+        if state.depth == 0 && !state.queue.is_empty() && state.running {
+            state.flush += 1;
+            // The thread can't stop while the state is locked, so the pipe is still open.
+            unsafe {
+                platform::unblock_ctrl_c();
+            }
+        }
+    }
+}
+
+/// Queue a received signal and return the signals to handle now, oldest first. Returns
+/// nothing while deferred.
+pub fn release(info: Option<SignalInfo>) -> VecDeque<SignalInfo> {
+    let mut state = STATE.lock().unwrap();
+    state.queue.extend(info);
+    if state.depth > 0 {
+        return VecDeque::new();
+    }
+    mem::take(&mut state.queue)
+}
+
+/// Whether a wake-up of the signal handling thread was meant to deliver the queue, rather
+/// than to stop it.
+pub fn take_flush() -> bool {
+    let mut state = STATE.lock().unwrap();
+    if state.flush == 0 {
+        return false;
+    }
+    state.flush -= 1;
+    true
+}
+
+/// The signal handling thread is about to start.
+pub fn start() {
+    STATE.lock().unwrap().running = true;
+}
+
+/// The signal handling thread stopped, forget about the queued signals.
+pub fn stop() {
+    let mut state = STATE.lock().unwrap();
+    state.running = false;
+    state.queue.clear();
+    state.flush = 0;
+}
@@ -1,15 +1,22 @@
 use std;
 use std::fmt;
+use std::sync::{Arc, Mutex};
 
 /// Ctrl-C error.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Signal could not be found from the system.
     NoSuchSignal(::SignalType),
     /// Ctrl-C signal handler already registered.
     MultipleHandlers,
+    /// Another signal handler is already installed for the signal, see
+    /// [InstallMode::Strict](enum.InstallMode.html#variant.Strict).
+    HandlerAlreadyInstalled(::SignalType),
     /// Unexpected system error.
     System(std::io::Error),
+    /// The signal handling thread could not be spawned.
+    ThreadSpawn(std::io::Error),
 }
 
 impl Error {
@@ -17,14 +24,16 @@ impl Error {
         match *self {
             Error::NoSuchSignal(_) => "Signal could not be found from the system",
             Error::MultipleHandlers => "Ctrl-C signal handler already registered",
+            Error::HandlerAlreadyInstalled(_) => "Another signal handler is already installed",
             Error::System(_) => "Unexpected system error",
+            Error::ThreadSpawn(_) => "Failed to spawn the signal handling thread",
         }
     }
 }
 
 impl From<::platform::Error> for Error {
     fn from(e: ::platform::Error) -> Error {
-        let system_error = std::io::Error::new(std::io::ErrorKind::Other, e);
+        let system_error = std::io::Error::other(e);
         Error::System(system_error)
     }
 }
@@ -42,8 +51,46 @@ impl std::error::Error for Error {
 
     fn cause(&self) -> Option<&dyn std::error::Error> {
         match *self {
-            Error::System(ref e) => Some(e),
+            Error::System(ref e) | Error::ThreadSpawn(ref e) => Some(e),
             _ => None,
         }
     }
 }
+
+type ErrorHandler = Arc<dyn Fn(&Error) + Send + Sync>;
+
+static ERROR_HANDLER: Mutex<Option<ErrorHandler>> = Mutex::new(None);
+
+/// Set a callback for errors that occur on the signal handling thread after the handler was
+/// registered, for example if reading the received signals fails.
+///
+/// The signal handling thread stops after such an error, as if the
+/// [Handler](struct.Handler.html) was dropped. Without a callback, the error is printed to
+/// stderr.
+///
+/// # Example
+/// ```no_run
+/// ctrlc::on_error(|err| eprintln!("Ctrl-C will no longer be handled: {}", err));
+/// ctrlc::set_handler(|| println!("Hello world!")).expect("Error setting Ctrl-C handler");
+/// ```
+///
+pub fn on_error<F>(f: F)
+where
+    F: Fn(&Error) + 'static + Send + Sync,
+{
+    *ERROR_HANDLER.lock().unwrap() = Some(Arc::new(f));
+}
+
+/// Pass an error of the signal handling thread to the callback set with
+/// [on_error()](fn.on_error.html).
+pub fn report<E: Into<Error>>(err: E) {
+    let err = err.into();
+    let handler = ERROR_HANDLER.lock().unwrap().clone();
+    match handler {
+        Some(handler) => handler(&err),
+        None => match err {
+            Error::System(ref e) | Error::ThreadSpawn(ref e) => eprintln!("{}: {}", err, e),
+            _ => eprintln!("{}", err),
+        },
+    }
+}
@@ -0,0 +1,218 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use error::{self, Error};
+use handler;
+use platform::{self, Signal};
+use std::process::Child;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use subscriber::{self, SubscriptionId};
+use SignalInfo;
+
+/// A process or process group that received signals are forwarded to.
+///
+/// Register with [forward_to()](fn.forward_to.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ForwardTarget {
+    /// The process with the given pid.
+    Process(u32),
+    /// The process group with the given id. For waiting, the group counts as exited once its
+    /// leader, the process of the same id, has exited.
+    ProcessGroup(u32),
+}
+
+impl ForwardTarget {
+    fn send(self, signal: Signal) -> Result<bool, Error> {
+        match self {
+            ForwardTarget::Process(pid) => platform::send_signal(pid, false, signal),
+            ForwardTarget::ProcessGroup(pgid) => platform::send_signal(pgid, true, signal),
+        }
+    }
+
+    fn in_own_group(self) -> bool {
+        match self {
+            ForwardTarget::Process(pid) => platform::in_own_group(pid, false),
+            ForwardTarget::ProcessGroup(pgid) => platform::in_own_group(pgid, true),
+        }
+    }
+
+    fn exited(self) -> bool {
+        match self {
+            ForwardTarget::Process(pid) | ForwardTarget::ProcessGroup(pid) => {
+                platform::child_exited(pid)
+            }
+        }
+    }
+}
+
+struct State {
+    targets: Vec<ForwardTarget>,
+    kill_after: Option<Duration>,
+    // The forwarding subscriber, registered while there are targets.
+    subscription: Option<SubscriptionId>,
+}
+
+impl State {
+    fn unsubscribe_if_done(&mut self) {
+        if self.targets.is_empty() {
+            if let Some(id) = self.subscription.take() {
+                subscriber::unsubscribe(id);
+            }
+        }
+    }
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    targets: Vec::new(),
+    kill_after: None,
+    subscription: None,
+});
+
+// How often the targets are checked for having exited.
+const WAIT_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Forward received signals to a process or process group.
+///
+/// When a signal is received, it is sent to every registered target first thing on the
+/// dedicated signal handling thread. The thread then waits for the targets that are child
+/// processes to exit, before the handler closure and the [subscribers](fn.subscribe.html)
+/// run. The children are not reaped, so `Child::wait()` still works afterwards. See
+/// [set_kill_after()](fn.set_kill_after.html) to not wait forever.
+///
+/// Targets that no longer exist are removed when a signal is received. Remove targets that
+/// were reaped with [stop_forwarding()](fn.stop_forwarding.html), their pid could be reused
+/// by an unrelated process. If no handler is registered yet, one is registered for the
+/// default signals, without a closure of its own.
+///
+/// Signals that came from the terminal, those with a [SignalInfo](struct.SignalInfo.html)
+/// without `pid`, are not sent again to targets in the process group of this process. The
+/// terminal sent them to its whole foreground process group already, they are only waited for.
+///
+/// With the `signalfd` backend, children inherit the blocked signals, and only die from a
+/// forwarded signal if they unblock it.
+///
+/// # Example
+/// ```no_run
+/// use std::process::Command;
+///
+/// let mut child = Command::new("sleep").arg("60").spawn().unwrap();
+/// ctrlc::forward_to_child(&child).expect("Error setting Ctrl-C handler");
+///
+/// // Returns once Ctrl-C was forwarded to the child, and it exited.
+/// child.wait().unwrap();
+/// ```
+///
+/// # Errors
+/// Will return an error if a system error occurred while setting the handler.
+///
+pub fn forward_to(target: ForwardTarget) -> Result<(), Error> {
+    // The handler may have been removed since the forwarding subscriber was added.
+    handler::ensure_installed()?;
+
+    let mut state = STATE.lock().unwrap();
+    if !state.targets.contains(&target) {
+        state.targets.push(target);
+    }
+
+    if state.subscription.is_none() {
+        // Forward before anything else runs.
+        match subscriber::subscribe_with_info(i32::MAX, Box::new(forward)) {
+            Ok(id) => state.subscription = Some(id),
+            Err(err) => {
+                state.targets.retain(|&t| t != target);
+                return Err(err);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Forward received signals to a child process.
+///
+/// See [forward_to()](fn.forward_to.html).
+///
+/// # Errors
+/// Will return an error if a system error occurred while setting the handler.
+///
+pub fn forward_to_child(child: &Child) -> Result<(), Error> {
+    forward_to(ForwardTarget::Process(child.id()))
+}
+
+/// Stop forwarding signals to a target. Returns `false` if it was not registered.
+pub fn stop_forwarding(target: ForwardTarget) -> bool {
+    let mut state = STATE.lock().unwrap();
+    let len = state.targets.len();
+    state.targets.retain(|&t| t != target);
+    state.unsubscribe_if_done();
+    state.targets.len() != len
+}
+
+/// Send `SIGKILL` to the targets that are still running `grace` after a signal was forwarded
+/// to them. With `None`, the default, the signal handling thread waits for them however long
+/// it takes.
+pub fn set_kill_after(grace: Option<Duration>) {
+    STATE.lock().unwrap().kill_after = grace;
+}
+
+/// Forward the signal to the targets and wait for them to exit.
+fn forward(info: SignalInfo) {
+    let platform_signal = match platform::platform_signal(&info.signal) {
+        Ok(platform_signal) => platform_signal,
+        Err(_) => return,
+    };
+
+    let (targets, kill_after) = {
+        let mut state = STATE.lock().unwrap();
+        let mut sent = Vec::with_capacity(state.targets.len());
+        state.targets.retain(|&target| {
+            // The terminal sent the signal to our process group, and with it to the target.
+            if info.pid.is_none() && target.in_own_group() {
+                sent.push(target);
+                return true;
+            }
+            match target.send(platform_signal) {
+                Ok(exists) => {
+                    if exists {
+                        sent.push(target);
+                    }
+                    exists
+                }
+                Err(err) => {
+                    error::report(err);
+                    true
+                }
+            }
+        });
+        state.unsubscribe_if_done();
+        (sent, state.kill_after)
+    };
+
+    let mut running = targets;
+    let mut deadline = kill_after.map(|grace| Instant::now() + grace);
+    loop {
+        running.retain(|&target| !target.exited());
+        if running.is_empty() {
+            return;
+        }
+
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            for &target in &running {
+                if let Err(err) = target.send(Signal::SIGKILL) {
+                    error::report(err);
+                }
+            }
+            deadline = None;
+        }
+
+        thread::sleep(WAIT_INTERVAL);
+    }
+}
@@ -0,0 +1,478 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use defer;
+use error::{self, Error};
+use panics;
+use platform;
+use policy;
+use std::mem;
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::thread;
+use subscriber;
+use SignalInfo;
+use SignalType;
+use DEFAULT_SIGNALS;
+
+/// A type-erased signal handler closure, as returned by
+/// [replace_handler()](fn.replace_handler.html).
+pub type BoxedHandler = Box<dyn FnMut(SignalType) + Send>;
+
+/// A type-erased signal handler closure that is told the details of the signal.
+pub type InfoHandler = Box<dyn FnMut(SignalInfo) + Send>;
+
+/// A handler closure that can be called from the signal handling thread while being replaced.
+pub type SharedHandler = Arc<Mutex<InfoHandler>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Signals are handled on the dedicated signal handling thread.
+    Thread,
+    /// Signals are handled on the dedicated signal handling thread, which was started for the
+    /// default signals by a subscriber, without a handler closure.
+    Implicit,
+    /// Signals are polled for, directly or by the async runtime integrations.
+    Threadless,
+}
+
+static INSTALLED: Mutex<Option<Mode>> = Mutex::new(None);
+
+/// How the OS handler and the signal handling thread are set up.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub signals: Vec<SignalType>,
+    pub thread_name: String,
+    pub stack_size: Option<usize>,
+    pub sa_restart: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            signals: DEFAULT_SIGNALS.to_vec(),
+            thread_name: "ctrl-c".into(),
+            stack_size: None,
+            sa_restart: true,
+        }
+    }
+}
+
+/// How to deal with signal handlers that were installed before ours, for example by a Python
+/// or JVM runtime embedded in the same process.
+///
+/// Set with [set_install_mode()](fn.set_install_mode.html). Only matters on Unix, on Windows
+/// the system calls every registered handler routine until one of them handles the event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InstallMode {
+    /// Replace the existing handlers.
+    #[default]
+    Overwrite,
+    /// Replace the existing handlers, but call them from ours after the signal was queued for
+    /// the handler thread. Handlers installed with `SA_SIGINFO` receive the original
+    /// `siginfo_t`.
+    Chain,
+    /// Fail with [Error::HandlerAlreadyInstalled](enum.Error.html#variant.HandlerAlreadyInstalled)
+    /// if any of the signals has a disposition other than the default one, including being
+    /// ignored.
+    Strict,
+}
+
+static INSTALL_MODE: Mutex<InstallMode> = Mutex::new(InstallMode::Overwrite);
+
+/// Set how handlers registered from now on deal with signal handlers installed before them.
+///
+/// # Example
+/// ```no_run
+/// use ctrlc::InstallMode;
+///
+/// ctrlc::set_install_mode(InstallMode::Chain);
+/// ctrlc::set_handler(|| println!("Hello world!")).expect("Error setting Ctrl-C handler");
+/// ```
+///
+pub fn set_install_mode(mode: InstallMode) {
+    *INSTALL_MODE.lock().unwrap() = mode;
+}
+
+// The closure called by the signal handling thread. It lives outside of the thread so that it
+// can be swapped by replace_handler(), and behind its own lock so that the slot is not locked
+// while the closure runs.
+static USER_HANDLER: Mutex<Option<SharedHandler>> = Mutex::new(None);
+
+// The configuration the signal handling thread was started with, while it runs.
+static RUNNING: Mutex<Option<Config>> = Mutex::new(None);
+
+/// A registered signal handler that is removed again when dropped.
+///
+/// Dropping the `Handler` restores the signal dispositions that were in place before it was
+/// installed, stops the dedicated signal handling thread and allows registering a new handler.
+/// Signals that were already received are handled before the thread stops.
+///
+/// If a [subscriber](fn.subscribe.html) already started the signal handling thread, a handler
+/// with the same configuration is added to that thread instead, and dropping it only removes the
+/// closure again. Likewise, if subscribers are still registered when the `Handler` is dropped,
+/// only its closure is removed and the thread keeps running for them.
+///
+/// # Example
+/// ```no_run
+/// let handler = ctrlc::Handler::install(|| println!("Hello world!"))
+///     .expect("Error setting Ctrl-C handler");
+///
+/// // Ctrl-C is handled until the handler goes out of scope.
+/// drop(handler);
+/// ```
+#[derive(Debug)]
+pub struct Handler {
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Handler {
+    /// Register signal handler for Ctrl-C.
+    ///
+    /// See [set_handler()](fn.set_handler.html).
+    ///
+    /// # Errors
+    /// Will return an error if another handler exists or if a system error occurred while
+    /// setting the handler.
+    ///
+    pub fn install<F>(mut user_handler: F) -> Result<Handler, Error>
+    where
+        F: FnMut() + 'static + Send,
+    {
+        Handler::install_with_signal(move |_| user_handler())
+    }
+
+    /// Register signal handler for Ctrl-C that is told which signal fired.
+    ///
+    /// See [set_handler_with_signal()](fn.set_handler_with_signal.html).
+    ///
+    /// # Errors
+    /// Will return an error if another handler exists or if a system error occurred while
+    /// setting the handler.
+    ///
+    pub fn install_with_signal<F>(user_handler: F) -> Result<Handler, Error>
+    where
+        F: FnMut(SignalType) + 'static + Send,
+    {
+        Handler::install_for(DEFAULT_SIGNALS, user_handler)
+    }
+
+    /// Register signal handler for the given signals.
+    ///
+    /// See [set_handler_for()](fn.set_handler_for.html).
+    ///
+    /// # Errors
+    /// Will return [Error::NoSuchSignal](enum.Error.html#variant.NoSuchSignal) if one of the
+    /// signals can't be handled on this platform, an error if another handler exists or if a
+    /// system error occurred while setting the handler.
+    ///
+    pub fn install_for<F>(signals: &[SignalType], user_handler: F) -> Result<Handler, Error>
+    where
+        F: FnMut(SignalType) + 'static + Send,
+    {
+        let config = Config {
+            signals: signals.to_vec(),
+            ..Config::default()
+        };
+        install_configured(&config, user_handler)
+    }
+
+    /// Register signal handler for Ctrl-C that is told the details of the signal.
+    ///
+    /// See [set_handler_with_info()](fn.set_handler_with_info.html).
+    ///
+    /// # Errors
+    /// Will return an error if another handler exists or if a system error occurred while
+    /// setting the handler.
+    ///
+    pub fn install_with_info<F>(user_handler: F) -> Result<Handler, Error>
+    where
+        F: FnMut(SignalInfo) + 'static + Send,
+    {
+        Handler::install_boxed(&Config::default(), Some(Box::new(user_handler)))
+    }
+
+    fn install_boxed(config: &Config, user_handler: Option<InfoHandler>) -> Result<Handler, Error> {
+        let mut installed = INSTALLED.lock().unwrap();
+        match *installed {
+            None => Handler::start(&mut installed, config, user_handler),
+            // Subscribers registered before the handler must not keep it from being set.
+            Some(Mode::Implicit) if running_config(config) => {
+                let mut slot = USER_HANDLER.lock().unwrap();
+                if slot.is_some() {
+                    return Err(Error::MultipleHandlers);
+                }
+                *slot = user_handler.map(|f| Arc::new(Mutex::new(f)));
+                Ok(Handler { thread: None })
+            }
+            Some(_) => Err(Error::MultipleHandlers),
+        }
+    }
+
+    fn start(
+        installed: &mut MutexGuard<Option<Mode>>,
+        config: &Config,
+        user_handler: Option<InfoHandler>,
+    ) -> Result<Handler, Error> {
+        let mode = *INSTALL_MODE.lock().unwrap();
+        unsafe {
+            platform::init_os_handler(&config.signals, mode, config.sa_restart)?;
+        }
+        **installed = Some(Mode::Thread);
+        *RUNNING.lock().unwrap() = Some(config.clone());
+
+        *USER_HANDLER.lock().unwrap() = user_handler.map(|f| Arc::new(Mutex::new(f)));
+
+        defer::start();
+
+        // Signals are read on a thread of their own, so that the policy sees them while the
+        // handler is still busy with an earlier one. `None` asks to deliver deferred signals.
+        let (tx, rx) = mpsc::channel::<Option<SignalInfo>>();
+        let reader = thread::Builder::new()
+            .name(format!("{}-reader", config.thread_name))
+            .spawn(move || loop {
+                let info = match unsafe { platform::block_ctrl_c() } {
+                    Ok(Some(info)) => {
+                        policy::apply();
+                        Some(info)
+                    }
+                    // Woken up to handle the signals received while deferred.
+                    Ok(None) if defer::take_flush() => None,
+                    Ok(None) => break,
+                    Err(err) => {
+                        error::report(err);
+                        break;
+                    }
+                };
+                let _ = tx.send(info);
+            });
+
+        let mut builder = thread::Builder::new().name(config.thread_name.clone());
+        if let Some(stack_size) = config.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+
+        // The thread stops once the reader did, and waits for it to end, so that a reader that
+        // does not block the signals of a later handler is not left running.
+        let spawned = reader.and_then(|reader| {
+            let reader = Arc::new(Mutex::new(Some(reader)));
+            let own_reader = reader.clone();
+            builder
+                .spawn(move || {
+                    for info in rx {
+                        for info in defer::release(info) {
+                            dispatch(info);
+                        }
+                    }
+                    if let Some(reader) = own_reader.lock().unwrap().take() {
+                        let _ = reader.join();
+                    }
+                    uninstall();
+                })
+                .inspect_err(|_| {
+                    unsafe {
+                        platform::unblock_ctrl_c();
+                    }
+                    if let Some(reader) = reader.lock().unwrap().take() {
+                        let _ = reader.join();
+                    }
+                })
+        });
+
+        let thread = match spawned {
+            Ok(thread) => thread,
+            Err(err) => {
+                defer::stop();
+                USER_HANDLER.lock().unwrap().take();
+                RUNNING.lock().unwrap().take();
+                unsafe {
+                    platform::deinit_os_handler();
+                    platform::deinit_thread();
+                }
+                **installed = None;
+                return Err(Error::ThreadSpawn(err));
+            }
+        };
+
+        Ok(Handler {
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for Handler {
+    fn drop(&mut self) {
+        let thread = match self.thread.take() {
+            Some(thread) => thread,
+            // Only the closure is ours, the thread keeps running for the subscribers.
+            None => {
+                USER_HANDLER.lock().unwrap().take();
+                return;
+            }
+        };
+
+        // Subscribers registered in the meantime keep the thread running, without the closure.
+        {
+            let mut installed = INSTALLED.lock().unwrap();
+            if !thread.is_finished() && !subscriber::is_empty() {
+                *installed = Some(Mode::Implicit);
+                USER_HANDLER.lock().unwrap().take();
+                return;
+            }
+        }
+
+        unsafe {
+            platform::unblock_ctrl_c();
+        }
+
+        // When dropped from within the handler, the thread cleans up after the handler returns.
+        if thread.thread().id() == thread::current().id() {
+            return;
+        }
+
+        // The thread only fails to clean up after itself if it panicked.
+        if thread.join().is_err() {
+            uninstall();
+        }
+
+        unsafe {
+            platform::deinit_thread();
+        }
+    }
+}
+
+/// Make sure a handler for the default signals is registered, without setting a closure for it.
+pub fn ensure_installed() -> Result<(), Error> {
+    let mut installed = INSTALLED.lock().unwrap();
+    match *installed {
+        Some(Mode::Thread) | Some(Mode::Implicit) => Ok(()),
+        Some(Mode::Threadless) => Err(Error::MultipleHandlers),
+        None => {
+            Handler::start(&mut installed, &Config::default(), None).map(mem::forget)?;
+            *installed = Some(Mode::Implicit);
+            Ok(())
+        }
+    }
+}
+
+/// Whether the signal handling thread runs with the same configuration as `config`. A
+/// handler with a different one would silently not get it.
+fn running_config(config: &Config) -> bool {
+    RUNNING.lock().unwrap().as_ref().is_some_and(|running| {
+        same_signals(&running.signals, &config.signals)
+            && running.thread_name == config.thread_name
+            && running.stack_size == config.stack_size
+            && running.sa_restart == config.sa_restart
+    })
+}
+
+/// Make sure the default signals are handled, by the signal handling thread or by polling.
+pub fn ensure_handled() -> Result<(), Error> {
+    if *INSTALLED.lock().unwrap() == Some(Mode::Threadless) {
+        return Ok(());
+    }
+    ensure_installed()
+}
+
+/// Whether both lists contain the same signals, in any order.
+fn same_signals(a: &[SignalType], b: &[SignalType]) -> bool {
+    a.iter().all(|s| b.contains(s)) && b.iter().all(|s| a.contains(s))
+}
+
+/// Register the OS handler for the default signals without starting the signal handling
+/// thread. Signals are then read with the nonblocking platform functions.
+///
+/// Stays registered for the rest of the program.
+pub fn install_threadless() -> Result<(), Error> {
+    let mut installed = INSTALLED.lock().unwrap();
+    match *installed {
+        Some(Mode::Threadless) => return Ok(()),
+        Some(Mode::Thread) | Some(Mode::Implicit) => return Err(Error::MultipleHandlers),
+        None => {}
+    }
+
+    unsafe {
+        platform::init_os_handler(DEFAULT_SIGNALS, *INSTALL_MODE.lock().unwrap(), true)?;
+        #[cfg(unix)]
+        {
+            if let Err(err) = platform::set_nonblocking() {
+                platform::deinit_os_handler();
+                return Err(err);
+            }
+        }
+    }
+    *installed = Some(Mode::Threadless);
+
+    Ok(())
+}
+
+/// Register a signal handler set up as described by `config`.
+pub fn install_configured<F>(config: &Config, mut user_handler: F) -> Result<Handler, Error>
+where
+    F: FnMut(SignalType) + 'static + Send,
+{
+    Handler::install_boxed(
+        config,
+        Some(Box::new(move |info: SignalInfo| user_handler(info.signal))),
+    )
+}
+
+/// Swap the closure called by the installed handler and return the previous one.
+///
+/// Registers a handler for the default signals if there is none.
+pub fn replace(mut user_handler: BoxedHandler) -> Result<Option<BoxedHandler>, Error> {
+    ensure_installed()?;
+    let user_handler: InfoHandler = Box::new(move |info: SignalInfo| user_handler(info.signal));
+    let old = USER_HANDLER
+        .lock()
+        .unwrap()
+        .replace(Arc::new(Mutex::new(user_handler)));
+    Ok(old.map(unshare))
+}
+
+/// Take back ownership of a closure, or wrap it if the signal handling thread is running it.
+///
+/// The returned closure only gets to see the signal, not the rest of its details.
+fn unshare(user_handler: SharedHandler) -> BoxedHandler {
+    match Arc::try_unwrap(user_handler) {
+        Ok(user_handler) => {
+            let mut user_handler = user_handler.into_inner().unwrap_or_else(|e| e.into_inner());
+            Box::new(move |signal| user_handler(SignalInfo::from(signal)))
+        }
+        Err(user_handler) => Box::new(move |signal| {
+            (user_handler.lock().unwrap_or_else(|e| e.into_inner()))(SignalInfo::from(signal))
+        }),
+    }
+}
+
+/// Call the handler closure and the subscribers, in order of their priority.
+fn dispatch(info: SignalInfo) {
+    let (before, after) = subscriber::snapshot();
+    let user_handler = USER_HANDLER.lock().unwrap().clone();
+
+    for f in before.iter().chain(user_handler.iter()).chain(after.iter()) {
+        // The lock is poisoned if the closure panicked before, it can still be called.
+        panics::catch(info.signal, || {
+            (f.lock().unwrap_or_else(|e| e.into_inner()))(info)
+        });
+    }
+
+    policy::apply_after(info.signal);
+}
+
+fn uninstall() {
+    defer::stop();
+    USER_HANDLER.lock().unwrap().take();
+    policy::reset();
+    let mut installed = INSTALLED.lock().unwrap();
+    RUNNING.lock().unwrap().take();
+    unsafe {
+        platform::deinit_os_handler();
+    }
+    *installed = None;
+}
@@ -0,0 +1,106 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use error::Error;
+use handler;
+use platform;
+use SignalType;
+use DEFAULT_SIGNALS;
+
+/// Ignores signals until dropped, e.g. while an interactive child process like an editor or a
+/// pager owns the terminal and should get Ctrl-C instead of us.
+///
+/// The signals are still received, but dropped as they arrive, before they reach the handler
+/// closure, the [subscribers](fn.subscribe.html), [try_recv()](fn.try_recv.html) or the async
+/// runtime integrations. Unlike setting the disposition to `SIG_IGN`, this does not leak into
+/// child processes spawned in the meantime, which would otherwise start with the signals
+/// ignored as well. Signals that are not handled by the registered handler keep their
+/// disposition, and guards can be nested and held on several threads at once.
+///
+/// With the `signalfd` backend, signals can only be dropped once they are read. The ones
+/// still pending when the last guard of a signal is dropped are discarded then, including
+/// any that arrived just before the guard was created.
+///
+/// # Example
+/// ```no_run
+/// use ctrlc::SignalType;
+/// use std::process::Command;
+///
+/// let mut child = Command::new("less").arg("README.md").spawn().unwrap();
+/// let guard = ctrlc::Ignore::new(&[SignalType::Ctrlc]).expect("Error setting Ctrl-C handler");
+/// child.wait().unwrap();
+/// drop(guard);
+/// ```
+#[must_use = "signals are only ignored while the guard is alive"]
+#[derive(Debug)]
+pub struct Ignore {
+    signals: Vec<SignalType>,
+}
+
+impl Ignore {
+    /// Ignore `signals` until the guard is dropped.
+    ///
+    /// If no handler is registered yet, one is registered for the default signals, without a
+    /// closure of its own, so that they don't end the process. After
+    /// [init_polling()](fn.init_polling.html), the signals are dropped before they can be
+    /// polled instead.
+    ///
+    /// # Errors
+    /// Will return [Error::NoSuchSignal](enum.Error.html#variant.NoSuchSignal) if one of the
+    /// signals can't be handled on this platform, or an error if a system error occurred while
+    /// setting the handler.
+    ///
+    pub fn new(signals: &[SignalType]) -> Result<Ignore, Error> {
+        handler::ensure_handled()?;
+
+        let mut guard = Ignore {
+            signals: Vec::with_capacity(signals.len()),
+        };
+        for signal in signals {
+            // Dropping the guard undoes the signals ignored so far.
+            platform::ignore(signal)?;
+            guard.signals.push(*signal);
+        }
+        Ok(guard)
+    }
+}
+
+impl Drop for Ignore {
+    fn drop(&mut self) {
+        for signal in &self.signals {
+            platform::unignore(signal);
+        }
+    }
+}
+
+/// Ignore Ctrl-C while `f` runs, e.g. while waiting for an interactive child process.
+///
+/// Ignores the signals the handler is registered for by default, see
+/// [Ignore](struct.Ignore.html).
+///
+/// # Example
+/// ```no_run
+/// use std::process::Command;
+///
+/// let mut editor = Command::new("vi").spawn().unwrap();
+/// let status = ctrlc::ignore_while(|| editor.wait())
+///     .expect("Error setting Ctrl-C handler")
+///     .unwrap();
+/// ```
+///
+/// # Errors
+/// Will return an error if a system error occurred while setting the handler.
+///
+pub fn ignore_while<F, R>(f: F) -> Result<R, Error>
+where
+    F: FnOnce() -> R,
+{
+    let _guard = Ignore::new(DEFAULT_SIGNALS)?;
+    Ok(f())
+}
@@ -44,26 +44,115 @@
 //! # Handling SIGTERM
 //! Handling of `SIGTERM` can be enabled with `termination` feature. If this is enabled,
 //! the handler specified by `set_handler()` will be executed for both `SIGINT` and `SIGTERM`.
+//! Use [set_handler_with_signal()](fn.set_handler_with_signal.html) to tell the two apart.
+//! Libraries should rather pick the signals at runtime with a [Builder](struct.Builder.html),
+//! which doesn't affect other crates in the dependency graph.
+//! Terminal hangups (`SIGHUP`) and `SIGQUIT` can be handled as well by passing
+//! [SignalType::Hangup](enum.SignalType.html#variant.Hangup) and
+//! [SignalType::Quit](enum.SignalType.html#variant.Quit) to
+//! [set_handler_for()](fn.set_handler_for.html).
+//!
+//! # signalfd backend
+//! On Linux, the `signalfd` feature replaces the signal handler and its self-pipe with
+//! `signalfd(2)`. The signals are blocked instead, so nothing runs in signal handler context.
+//! Blocking only applies to the thread that registers the handler and the threads it spawns
+//! afterwards, register it at the start of `main` before spawning other threads. A signal
+//! that arrives at a thread that does not block it gets its default disposition. Dropping a
+//! [Handler](struct.Handler.html) unblocks the signals again if it is dropped on the thread
+//! that installed it, threads spawned in the meantime keep them blocked.
+//! [InstallMode::Chain](enum.InstallMode.html#variant.Chain) is not supported. Child
+//! processes inherit the blocked signals, unblock them in `CommandExt::pre_exec()` if the
+//! children should be interruptible. It can't be combined with the `tokio` and `async-std`
+//! features, which install the handler on whichever runtime thread gets to it first.
+//!
+//! # Polling
+//! Programs with a main loop of their own can do without the signal handling thread. After
+//! [init_polling()](fn.init_polling.html), received signals are queued until they are taken
+//! with [try_recv()](fn.try_recv.html).
+//!
+//! On Unix, a [Notifier](struct.Notifier.html) provides a file descriptor to wait for with
+//! `poll(2)`, `epoll(7)` or [mio](https://docs.rs/mio) (with the `mio` feature) instead.
+//!
+//! # Async runtimes
+//! The `tokio` and `async-std` features add the [tokio](tokio/index.html) and
+//! [async_std](async_std/index.html) modules, which receive the signals from within the
+//! runtime instead of on a dedicated thread.
 //!
 
-#[macro_use]
+// The signals would only be blocked on one of the threads of the runtime.
+#[cfg(all(
+    feature = "signalfd",
+    any(feature = "tokio", feature = "async-std"),
+    any(target_os = "linux", target_os = "android")
+))]
+compile_error!("the signalfd feature can't be combined with the tokio or async-std features");
 
+mod builder;
+pub use builder::Builder;
+mod channel;
+pub use channel::*;
+mod defer;
+pub use defer::{defer, DeferGuard};
 mod error;
+#[cfg(unix)]
+mod forward;
+#[cfg(unix)]
+pub use forward::{forward_to, forward_to_child, set_kill_after, stop_forwarding, ForwardTarget};
+mod handler;
+mod ignore;
+pub use ignore::{ignore_while, Ignore};
 mod platform;
 pub use platform::Signal;
+#[cfg(unix)]
+mod notifier;
+#[cfg(unix)]
+pub use notifier::Notifier;
+mod poll;
+pub use poll::*;
+mod panics;
+pub use panics::{set_panic_action, set_panic_hook, PanicAction};
+mod policy;
+pub use policy::{exit_by_signal, set_policy, Policy};
+mod shutdown;
+pub use shutdown::{
+    on_shutdown, on_shutdown_with_dependencies, on_shutdown_with_timeout, run_shutdown_hooks,
+    shutdown_summary, ShutdownSummary,
+};
 mod signal;
 pub use signal::*;
+mod stats;
+pub use stats::{stats, Stats};
+mod subscriber;
+pub use subscriber::SubscriptionId;
+mod token;
+pub use token::ShutdownToken;
+mod watchdog;
+pub use watchdog::TimeoutAction;
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+mod stream;
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub use stream::Stream;
+#[cfg(feature = "async-std")]
+pub mod async_std;
+#[cfg(feature = "tokio")]
+pub mod tokio;
 
-pub use error::Error;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::thread;
+pub use error::{on_error, Error};
+pub use handler::{set_install_mode, BoxedHandler, Handler, InstallMode};
+use std::mem;
+use std::time::Duration;
 
-static INIT: AtomicBool = AtomicBool::new(false);
+#[cfg(not(feature = "termination"))]
+const DEFAULT_SIGNALS: &[SignalType] = &[SignalType::Ctrlc];
+
+#[cfg(feature = "termination")]
+const DEFAULT_SIGNALS: &[SignalType] = &[SignalType::Ctrlc, SignalType::Termination];
 
 /// Register signal handler for Ctrl-C.
 ///
 /// Starts a new dedicated signal handling thread. Should only be called once,
-/// typically at the start of your program.
+/// typically at the start of your program. Use [Handler](struct.Handler.html) for
+/// a handler that can be removed again.
 ///
 /// # Example
 /// ```no_run
@@ -72,7 +161,8 @@ static INIT: AtomicBool = AtomicBool::new(false);
 ///
 /// # Warning
 /// On Unix, any existing `SIGINT`, `SIGTERM`(if termination feature is enabled) or `SA_SIGINFO`
-/// posix signal handlers will be overwritten. On Windows, multiple handler routines are allowed,
+/// posix signal handlers will be overwritten, unless configured otherwise with
+/// [set_install_mode()](fn.set_install_mode.html). On Windows, multiple handler routines are allowed,
 /// but they are called on a last-registered, first-called basis until the signal is handled.
 ///
 /// On Unix, signal dispositions and signal handlers are inherited by child processes created via
@@ -84,35 +174,260 @@ static INIT: AtomicBool = AtomicBool::new(false);
 /// system error occurred while setting the handler.
 ///
 /// # Panics
-/// Any panic in the handler will not be caught and will cause the signal handler thread to stop.
+/// A panic in the handler is caught and the signal handling thread keeps handling signals,
+/// unless configured otherwise with [set_panic_action()](fn.set_panic_action.html).
 ///
 pub fn set_handler<F>(mut user_handler: F) -> Result<(), Error>
 where
-    F: FnMut() -> () + 'static + Send,
+    F: FnMut() + 'static + Send,
+{
+    set_handler_with_signal(move |_| user_handler())
+}
+
+/// Register signal handler for Ctrl-C that is told which signal fired.
+///
+/// Works like [set_handler()](fn.set_handler.html), but the handler receives the
+/// [SignalType](enum.SignalType.html) of the signal that triggered it. This is mostly
+/// useful together with the `termination` feature, to react to `SIGTERM` differently
+/// than to an interactive `Ctrl+C`.
+///
+/// # Example
+/// ```no_run
+/// use ctrlc::SignalType;
+///
+/// ctrlc::set_handler_with_signal(|signal| match signal {
+///     SignalType::Ctrlc => println!("Interrupted"),
+///     SignalType::Termination => println!("Terminated"),
+///     _ => {}
+/// })
+/// .expect("Error setting Ctrl-C handler");
+/// ```
+///
+/// # Errors
+/// Will return an error if another `ctrlc::set_handler()` handler exists or if a
+/// system error occurred while setting the handler.
+///
+/// # Panics
+/// A panic in the handler is caught and the signal handling thread keeps handling signals,
+/// unless configured otherwise with [set_panic_action()](fn.set_panic_action.html).
+///
+pub fn set_handler_with_signal<F>(user_handler: F) -> Result<(), Error>
+where
+    F: FnMut(SignalType) + 'static + Send,
+{
+    set_handler_for(DEFAULT_SIGNALS, user_handler)
+}
+
+/// Register signal handler for Ctrl-C that is told the details of the signal.
+///
+/// Works like [set_handler_with_signal()](fn.set_handler_with_signal.html), but the handler
+/// receives a [SignalInfo](struct.SignalInfo.html). On Unix, this tells who sent the signal,
+/// for example to log whether a `SIGTERM` came from an operator or from the service manager.
+///
+/// # Example
+/// ```no_run
+/// ctrlc::set_handler_with_info(|info| match info.pid {
+///     Some(pid) => println!("{:?} sent by process {}", info.signal, pid),
+///     None => println!("{:?}", info.signal),
+/// })
+/// .expect("Error setting Ctrl-C handler");
+/// ```
+///
+/// # Errors
+/// Will return an error if another `ctrlc::set_handler()` handler exists or if a
+/// system error occurred while setting the handler.
+///
+/// # Panics
+/// A panic in the handler is caught and the signal handling thread keeps handling signals,
+/// unless configured otherwise with [set_panic_action()](fn.set_panic_action.html).
+///
+pub fn set_handler_with_info<F>(user_handler: F) -> Result<(), Error>
+where
+    F: FnMut(SignalInfo) + 'static + Send,
+{
+    Handler::install_with_info(user_handler).map(mem::forget)
+}
+
+/// Register signal handler for the given signals.
+///
+/// Works like [set_handler_with_signal()](fn.set_handler_with_signal.html), but instead of
+/// `Ctrl+C` (and `SIGTERM` with the `termination` feature) the handler is executed for every
+/// signal in `signals`. Platform specific signals, like `SIGPIPE`, can be requested with
+/// [SignalType::Other](enum.SignalType.html#variant.Other).
+///
+/// If registering the handler fails for one of the signals, the signals registered before it
+/// are restored to what they were.
+///
+/// # Example
+/// ```no_run
+/// # #[cfg(unix)]
+/// # fn main() {
+/// use ctrlc::SignalType;
+///
+/// let signals = [
+///     SignalType::Ctrlc,
+///     SignalType::Termination,
+///     SignalType::Hangup,
+///     SignalType::Quit,
+/// ];
+/// ctrlc::set_handler_for(&signals, |signal| match signal {
+///     SignalType::Hangup => println!("Terminal closed, shutting down"),
+///     _ => println!("Shutting down"),
+/// })
+/// .expect("Error setting signal handler");
+/// # }
+/// # #[cfg(windows)]
+/// # fn main() {}
+/// ```
+///
+/// # Errors
+/// Will return [Error::NoSuchSignal](enum.Error.html#variant.NoSuchSignal) if one of the
+/// signals can't be handled on this platform, an error if another `ctrlc::set_handler()`
+/// handler exists or if a system error occurred while setting the handler.
+///
+/// # Panics
+/// A panic in the handler is caught and the signal handling thread keeps handling signals,
+/// unless configured otherwise with [set_panic_action()](fn.set_panic_action.html).
+///
+pub fn set_handler_for<F>(signals: &[SignalType], user_handler: F) -> Result<(), Error>
+where
+    F: FnMut(SignalType) + 'static + Send,
+{
+    Handler::install_for(signals, user_handler).map(mem::forget)
+}
+
+/// Register signal handler for Ctrl-C with a deadline for shutting down.
+///
+/// Works like [set_handler_with_signal()](fn.set_handler_with_signal.html), but when the first
+/// signal is received, a watchdog is started before the handler is executed. If the process is
+/// still running after `timeout`, the watchdog applies `action`. This keeps a handler that hangs
+/// while shutting down from keeping the process alive.
+///
+/// # Example
+/// ```no_run
+/// use ctrlc::TimeoutAction;
+/// use std::time::Duration;
+///
+/// ctrlc::set_handler_with_timeout(
+///     |_| {
+///         println!("Shutting down...");
+///         std::process::exit(0);
+///     },
+///     Duration::from_secs(10),
+///     TimeoutAction::Reraise,
+/// )
+/// .expect("Error setting Ctrl-C handler");
+/// ```
+///
+/// # Errors
+/// Will return an error if another `ctrlc::set_handler()` handler exists or if a
+/// system error occurred while setting the handler.
+///
+/// # Panics
+/// A panic in the handler is caught and the signal handling thread keeps handling signals,
+/// unless configured otherwise with [set_panic_action()](fn.set_panic_action.html).
+///
+pub fn set_handler_with_timeout<F>(
+    mut user_handler: F,
+    timeout: Duration,
+    action: TimeoutAction,
+) -> Result<(), Error>
+where
+    F: FnMut(SignalType) + 'static + Send,
 {
-    if INIT.compare_and_swap(false, true, Ordering::SeqCst) {
-        return Err(Error::MultipleHandlers);
-    }
-
-    unsafe {
-        match platform::init_os_handler() {
-            Ok(_) => {}
-            Err(err) => {
-                INIT.store(false, Ordering::SeqCst);
-                return Err(err.into());
-            }
+    let mut started = false;
+    set_handler_with_signal(move |signal| {
+        if !started {
+            started = true;
+            watchdog::start(timeout, action, signal);
         }
-    }
-
-    thread::Builder::new()
-        .name("ctrl-c".into())
-        .spawn(move || loop {
-            unsafe {
-                platform::block_ctrl_c().expect("Critical system error while waiting for Ctrl-C");
-            }
-            user_handler();
-        })
-        .expect("failed to spawn thread");
-
-    Ok(())
+        user_handler(signal);
+    })
+}
+
+/// Replace the closure executed by the signal handler.
+///
+/// Swaps the closure the dedicated signal handling thread calls, without reinstalling the OS
+/// signal handlers, and returns the previous closure so that it can be chained. If no handler
+/// is registered yet, this works like [set_handler_with_signal()](fn.set_handler_with_signal.html)
+/// and returns `None`. This way a library and the binary using it can both set a handler
+/// regardless of which one does it first.
+///
+/// # Example
+/// ```no_run
+/// let mut previous = ctrlc::replace_handler(|_| println!("Hello world!"))
+///     .expect("Error setting Ctrl-C handler");
+///
+/// ctrlc::replace_handler(move |signal| {
+///     println!("Cleaning up");
+///     if let Some(ref mut previous) = previous {
+///         previous(signal);
+///     }
+/// })
+/// .expect("Error setting Ctrl-C handler");
+/// ```
+///
+/// # Errors
+/// Will return an error if a system error occurred while setting the handler.
+///
+pub fn replace_handler<F>(user_handler: F) -> Result<Option<BoxedHandler>, Error>
+where
+    F: FnMut(SignalType) + 'static + Send,
+{
+    handler::replace(Box::new(user_handler))
+}
+
+/// Subscribe to Ctrl-C signals.
+///
+/// Unlike [set_handler()](fn.set_handler.html), any number of subscribers can be registered.
+/// They are called one after another on the dedicated signal handling thread, after the
+/// handler closure in the order they were registered. If no handler is registered yet, one
+/// is registered for the default signals, without a closure of its own. A handler for the
+/// default signals can still be set afterwards, with [set_handler()](fn.set_handler.html) or
+/// [Handler](struct.Handler.html).
+///
+/// Use [subscribe_with_priority()](fn.subscribe_with_priority.html) to control the order and
+/// [unsubscribe()](fn.unsubscribe.html) to remove the subscriber again.
+///
+/// # Example
+/// ```no_run
+/// let id = ctrlc::subscribe(|_| println!("Closing database connections"))
+///     .expect("Error subscribing to Ctrl-C");
+///
+/// ctrlc::unsubscribe(id);
+/// ```
+///
+/// # Errors
+/// Will return an error if a system error occurred while setting the handler.
+///
+pub fn subscribe<F>(user_handler: F) -> Result<SubscriptionId, Error>
+where
+    F: FnMut(SignalType) + 'static + Send,
+{
+    subscribe_with_priority(0, user_handler)
+}
+
+/// Subscribe to Ctrl-C signals with the given priority.
+///
+/// Subscribers are called in order of descending priority, subscribers of equal priority in
+/// the order they were registered. The handler closure set with
+/// [set_handler()](fn.set_handler.html) or [replace_handler()](fn.replace_handler.html)
+/// counts as the first subscriber of priority `0`.
+///
+/// # Errors
+/// Will return an error if a system error occurred while setting the handler.
+///
+pub fn subscribe_with_priority<F>(priority: i32, user_handler: F) -> Result<SubscriptionId, Error>
+where
+    F: FnMut(SignalType) + 'static + Send,
+{
+    subscriber::subscribe(priority, Box::new(user_handler))
+}
+
+/// Remove a subscriber registered with [subscribe()](fn.subscribe.html).
+///
+/// Returns `false` if the subscriber was already removed.
+///
+pub fn unsubscribe(id: SubscriptionId) -> bool {
+    subscriber::unsubscribe(id)
 }
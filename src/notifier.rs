@@ -0,0 +1,130 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+#[cfg(feature = "mio")]
+extern crate mio;
+
+use error::Error;
+use handler;
+use platform;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use SignalType;
+
+#[cfg(feature = "mio")]
+use self::mio::event::Source;
+#[cfg(feature = "mio")]
+use self::mio::unix::SourceFd;
+#[cfg(feature = "mio")]
+use self::mio::{Interest, Registry, Token};
+#[cfg(feature = "mio")]
+use std::io;
+
+/// A file descriptor that becomes readable when a Ctrl-C signal is received, for use in event
+/// loops built on `poll(2)`, `epoll(7)` or `kqueue(2)`.
+///
+/// The descriptor is nonblocking. Once it is readable, take the received signals with
+/// [drain()](#method.drain). With the `mio` feature, the notifier can be registered with a
+/// `mio::Registry` directly.
+///
+/// Like [init_polling()](fn.init_polling.html), this registers the OS signal handler for the
+/// rest of the program and can't be combined with [set_handler()](fn.set_handler.html). If
+/// there are several notifiers, each signal is received by one of them.
+///
+/// # Example
+/// ```no_run
+/// # #[cfg(unix)]
+/// # fn main() {
+/// use std::os::unix::io::AsRawFd;
+///
+/// let mut notifier = ctrlc::Notifier::new().expect("Error setting Ctrl-C handler");
+/// let fd = notifier.as_raw_fd();
+///
+/// // Wait for `fd` to become readable next to the other descriptors, then:
+/// for signal in notifier.drain().expect("Error reading Ctrl-C signals") {
+///     println!("Received {:?}", signal);
+/// }
+/// # }
+/// # #[cfg(windows)]
+/// # fn main() {}
+/// ```
+#[derive(Debug)]
+pub struct Notifier {
+    fd: OwnedFd,
+}
+
+impl Notifier {
+    /// Register the OS signal handler for Ctrl-C and create a notifier for it.
+    ///
+    /// # Errors
+    /// Will return an error if another `ctrlc::set_handler()` handler exists or if a
+    /// system error occurred while setting the handler.
+    ///
+    pub fn new() -> Result<Notifier, Error> {
+        handler::install_threadless()?;
+
+        // Every notifier gets its own descriptor, so that it can be closed independently.
+        let fd = unsafe { BorrowedFd::borrow_raw(platform::read_fd()) }
+            .try_clone_to_owned()
+            .map_err(Error::System)?;
+        Ok(Notifier { fd })
+    }
+
+    /// Take all signals that were received, without blocking.
+    ///
+    /// Returns an empty `Vec` if there are none. Reads until there is nothing left, so this
+    /// works with edge-triggered notification as well.
+    ///
+    /// # Errors
+    /// Will return an error if a system error occurred while reading the signals.
+    ///
+    pub fn drain(&mut self) -> Result<Vec<SignalType>, Error> {
+        let mut signals = Vec::new();
+        while let Some(info) = unsafe { platform::poll_ctrl_c()? } {
+            signals.push(info.signal);
+        }
+        Ok(signals)
+    }
+}
+
+impl AsRawFd for Notifier {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl AsFd for Notifier {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+#[cfg(feature = "mio")]
+impl Source for Notifier {
+    fn register(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        SourceFd(&self.as_raw_fd()).deregister(registry)
+    }
+}
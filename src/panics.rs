@@ -0,0 +1,89 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::sync::{Arc, Mutex};
+use SignalType;
+
+/// What the signal handling thread does when a handler panics.
+///
+/// Set with [set_panic_action()](fn.set_panic_action.html). Either way, the panic is first
+/// reported to the hook set with [set_panic_hook()](fn.set_panic_hook.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicAction {
+    /// Keep handling signals.
+    #[default]
+    Continue,
+    /// Abort the process.
+    Abort,
+}
+
+type PanicHook = Arc<dyn Fn(SignalType, &(dyn Any + Send)) + Send + Sync>;
+
+static ACTION: Mutex<PanicAction> = Mutex::new(PanicAction::Continue);
+static HOOK: Mutex<Option<PanicHook>> = Mutex::new(None);
+
+/// Set what the signal handling thread does when a handler panics.
+///
+/// # Example
+/// ```no_run
+/// use ctrlc::PanicAction;
+///
+/// ctrlc::set_panic_action(PanicAction::Abort);
+/// ctrlc::set_handler(|| panic!("Aborts the process")).expect("Error setting Ctrl-C handler");
+/// ```
+///
+pub fn set_panic_action(action: PanicAction) {
+    *ACTION.lock().unwrap() = action;
+}
+
+/// Set a hook that is called on the signal handling thread when a handler panics, with the
+/// signal that was being handled and the panic payload.
+///
+/// The panic was already printed by the standard library's panic hook, this is for reporting
+/// it elsewhere, e.g. to a logger.
+///
+/// # Example
+/// ```no_run
+/// ctrlc::set_panic_hook(|signal, payload| {
+///     let message = payload
+///         .downcast_ref::<&str>()
+///         .copied()
+///         .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+///         .unwrap_or("Box<dyn Any>");
+///     eprintln!("Handler for {:?} panicked: {}", signal, message);
+/// });
+/// ```
+///
+pub fn set_panic_hook<F>(hook: F)
+where
+    F: Fn(SignalType, &(dyn Any + Send)) + 'static + Send + Sync,
+{
+    *HOOK.lock().unwrap() = Some(Arc::new(hook));
+}
+
+/// Call `f`, reporting a panic and applying the [PanicAction] instead of unwinding further.
+pub fn catch<F: FnOnce()>(signal: SignalType, f: F) {
+    let payload = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(()) => return,
+        Err(payload) => payload,
+    };
+
+    let hook = HOOK.lock().unwrap().clone();
+    if let Some(hook) = hook {
+        // A panicking hook must not stop the signal handling thread either.
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| hook(signal, &*payload)));
+    }
+
+    if *ACTION.lock().unwrap() == PanicAction::Abort {
+        process::abort();
+    }
+}
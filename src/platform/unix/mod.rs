@@ -9,11 +9,23 @@
 
 extern crate nix;
 
+use self::nix::libc;
 use self::nix::unistd;
 use error::Error as CtrlcError;
+use std::io;
+use std::mem;
 use std::os::unix::io::RawFd;
+use SignalType;
 
-static mut PIPE: (RawFd, RawFd) = (-1, -1);
+#[cfg(not(all(feature = "signalfd", any(target_os = "linux", target_os = "android"))))]
+mod pipe;
+#[cfg(all(feature = "signalfd", any(target_os = "linux", target_os = "android")))]
+mod signalfd;
+
+#[cfg(not(all(feature = "signalfd", any(target_os = "linux", target_os = "android"))))]
+pub use self::pipe::*;
+#[cfg(all(feature = "signalfd", any(target_os = "linux", target_os = "android")))]
+pub use self::signalfd::*;
 
 /// Platform specific error type
 pub type Error = nix::Error;
@@ -21,10 +33,14 @@ pub type Error = nix::Error;
 /// Platform specific signal type
 pub type Signal = nix::sys::signal::Signal;
 
-extern "C" fn os_handler(_: nix::libc::c_int) {
-    // Assuming this always succeeds. Can't really handle errors in any meaningful way.
-    unsafe {
-        let _ = unistd::write(PIPE.1, &[0u8]);
+/// Map a platform signal to its cross-platform representation.
+fn signal_type(signal: Signal) -> SignalType {
+    match signal {
+        Signal::SIGINT => SignalType::Ctrlc,
+        Signal::SIGTERM => SignalType::Termination,
+        Signal::SIGHUP => SignalType::Hangup,
+        Signal::SIGQUIT => SignalType::Quit,
+        other => SignalType::Other(other),
     }
 }
 
@@ -66,83 +82,144 @@ fn pipe2(flags: nix::fcntl::OFlag) -> nix::Result<(RawFd, RawFd)> {
     unistd::pipe2(flags)
 }
 
-/// Register os signal handler.
+/// Map a cross-platform signal to the platform signal it is delivered as.
+pub fn platform_signal(signal: &SignalType) -> Result<Signal, CtrlcError> {
+    let platform_signal = match *signal {
+        SignalType::Ctrlc => Signal::SIGINT,
+        SignalType::Termination => Signal::SIGTERM,
+        SignalType::Hangup => Signal::SIGHUP,
+        SignalType::Quit => Signal::SIGQUIT,
+        SignalType::Other(signal) => signal,
+    };
+
+    match platform_signal {
+        // SIGKILL and SIGSTOP can't be caught, and returning from a handler of a synchronous
+        // fault would only make the faulting instruction run again.
+        Signal::SIGKILL
+        | Signal::SIGSTOP
+        | Signal::SIGSEGV
+        | Signal::SIGBUS
+        | Signal::SIGILL
+        | Signal::SIGFPE => Err(CtrlcError::NoSuchSignal(*signal)),
+        platform_signal => Ok(platform_signal),
+    }
+}
+
+/// Map cross-platform signals to the platform signals they are delivered as, without
+/// duplicates.
+fn platform_signals(signals: &[SignalType]) -> Result<Vec<Signal>, CtrlcError> {
+    let mut platform_signals = Vec::with_capacity(signals.len());
+    for signal in signals {
+        let platform_signal = platform_signal(signal)?;
+        if !platform_signals.contains(&platform_signal) {
+            platform_signals.push(platform_signal);
+        }
+    }
+    Ok(platform_signals)
+}
+
+/// Fail with `HandlerAlreadyInstalled` if one of the signals has a disposition other than the
+/// default one. Only reads the dispositions, so that a signal is never handled by us before
+/// the check failed.
+fn check_default(signals: &[Signal]) -> Result<(), CtrlcError> {
+    for &signal in signals {
+        let mut old: libc::sigaction = unsafe { mem::zeroed() };
+        if unsafe { libc::sigaction(signal as libc::c_int, std::ptr::null(), &mut old) } != 0 {
+            return Err(nix::Error::last().into());
+        }
+        if old.sa_sigaction != libc::SIG_DFL {
+            return Err(CtrlcError::HandlerAlreadyInstalled(signal_type(signal)));
+        }
+    }
+    Ok(())
+}
+
+/// Send `signal` to the process `pid`, or to the process group `pid` if `group` is set.
 ///
-/// Must be called before calling [`block_ctrl_c()`](fn.block_ctrl_c.html)
-/// and should only be called once.
+/// Returns `false` if there is no such process or process group.
 ///
 /// # Errors
 /// Will return an error if a system error occurred.
 ///
-#[inline]
-pub unsafe fn init_os_handler() -> Result<(), Error> {
-    use self::nix::fcntl;
+pub fn send_signal(pid: u32, group: bool, signal: Signal) -> Result<bool, CtrlcError> {
+    use self::nix::errno::Errno;
     use self::nix::sys::signal;
 
-    PIPE = pipe2(fcntl::OFlag::O_CLOEXEC)?;
-
-    let close_pipe = |e: nix::Error| -> Error {
-        // Try to close the pipes. close() should not fail,
-        // but if it does, there isn't much we can do
-        let _ = unistd::close(PIPE.1);
-        let _ = unistd::close(PIPE.0);
-        e
+    let pid = unistd::Pid::from_raw(pid as libc::pid_t);
+    let result = if group {
+        signal::killpg(pid, signal)
+    } else {
+        signal::kill(pid, signal)
     };
 
-    // Make sure we never block on write in the os handler.
-    if let Err(e) = fcntl::fcntl(PIPE.1, fcntl::FcntlArg::F_SETFL(fcntl::OFlag::O_NONBLOCK)) {
-        return Err(close_pipe(e));
+    match result {
+        Ok(()) => Ok(true),
+        Err(nix::Error::Sys(Errno::ESRCH)) => Ok(false),
+        Err(e) => Err(e.into()),
     }
+}
 
-    let handler = signal::SigHandler::Handler(os_handler);
-    let new_action = signal::SigAction::new(
-        handler,
-        signal::SaFlags::SA_RESTART,
-        signal::SigSet::empty(),
-    );
-
-    let _old = match signal::sigaction(signal::Signal::SIGINT, &new_action) {
-        Ok(old) => old,
-        Err(e) => return Err(close_pipe(e)),
+/// Whether the process `pid`, or the process group `pid` if `group` is set, belongs to the
+/// process group of this process. Processes that no longer exist don't.
+pub fn in_own_group(pid: u32, group: bool) -> bool {
+    let pid = unistd::Pid::from_raw(pid as libc::pid_t);
+    let pgid = if group {
+        pid
+    } else {
+        match unistd::getpgid(Some(pid)) {
+            Ok(pgid) => pgid,
+            Err(_) => return false,
+        }
     };
+    pgid == unistd::getpgrp()
+}
 
-    #[cfg(feature = "termination")]
-    match signal::sigaction(signal::Signal::SIGTERM, &new_action) {
-        Ok(_) => {}
-        Err(e) => {
-            signal::sigaction(signal::Signal::SIGINT, &_old).unwrap();
-            return Err(close_pipe(e));
+/// Whether the child process `pid` exited, without reaping it. Processes that are not
+/// children of this process, or were already reaped, count as exited.
+pub fn child_exited(pid: u32) -> bool {
+    loop {
+        let mut info = unsafe { mem::zeroed::<libc::siginfo_t>() };
+        let result = unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+            )
+        };
+        if result == 0 {
+            // With WNOHANG, si_pid stays zero if the child is still running.
+            return unsafe { info.si_pid() } != 0;
+        }
+        if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            return true;
         }
     }
-
-    // TODO: Maybe throw an error if old action is not SigDfl.
-
-    Ok(())
 }
 
-/// Blocks until a Ctrl-C signal is received.
+/// Restore the default disposition of the signal, unblock it and raise it.
 ///
-/// Must be called after calling [`init_os_handler()`](fn.init_os_handler.html).
+/// Returns if the default disposition of the signal does not end the process.
 ///
 /// # Errors
-/// Will return an error if a system error occurred.
+/// Will return an error if the signal can't be handled or if a system error occurred.
 ///
 #[inline]
-pub unsafe fn block_ctrl_c() -> Result<(), CtrlcError> {
-    use std::io;
-    let mut buf = [0u8];
+pub unsafe fn raise_default(signal: &SignalType) -> Result<(), CtrlcError> {
+    use self::nix::sys::signal;
 
-    // TODO: Can we safely convert the pipe fd into a std::io::Read
-    // with std::os::unix::io::FromRawFd, this would handle EINTR
-    // and everything for us.
-    loop {
-        match unistd::read(PIPE.0, &mut buf[..]) {
-            Ok(1) => break,
-            Ok(_) => return Err(CtrlcError::System(io::ErrorKind::UnexpectedEof.into())),
-            Err(nix::Error::Sys(nix::errno::Errno::EINTR)) => {}
-            Err(e) => return Err(e.into()),
-        }
-    }
+    let platform_signal = platform_signal(signal)?;
+    let default = signal::SigAction::new(
+        signal::SigHandler::SigDfl,
+        signal::SaFlags::empty(),
+        signal::SigSet::empty(),
+    );
+    signal::sigaction(platform_signal, &default)?;
+
+    let mut set = signal::SigSet::empty();
+    set.add(platform_signal);
+    set.thread_unblock()?;
 
+    signal::raise(platform_signal)?;
     Ok(())
 }
@@ -0,0 +1,530 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use super::nix::libc::{self, c_int, c_void, siginfo_t};
+use super::nix::sys::signal::SigHandler;
+use super::nix::unistd;
+use super::{check_default, nix, pipe2, platform_signal, platform_signals, signal_type, Signal};
+use error::Error as CtrlcError;
+use std::convert::TryFrom;
+use std::hint;
+use std::mem;
+use std::os::unix::io::RawFd;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use InstallMode;
+use SignalInfo;
+use SignalType;
+use Stats;
+
+static mut PIPE: (RawFd, RawFd) = (-1, -1);
+
+// Signal dispositions replaced by init_os_handler(), restored by deinit_os_handler().
+static OLD_ACTIONS: Mutex<Vec<(Signal, nix::sys::signal::SigAction)>> = Mutex::new(Vec::new());
+
+// Signals that were received but not read yet, from the pipe or OVERFLOW.
+static PENDING: AtomicUsize = AtomicUsize::new(0);
+
+// Signals written to the pipe that were not read yet.
+static QUEUED: AtomicUsize = AtomicUsize::new(0);
+
+// Messages written to the pipe by os_handler() hold the signal number, si_code, si_pid and
+// si_uid, in native byte order. They are smaller than PIPE_BUF, so they are always written
+// and read as a whole.
+const MESSAGE_SIZE: usize = 16;
+
+// Larger than the number of signals on any supported platform.
+const MAX_SIGNALS: usize = 128;
+
+// The handlers os_handler() chains to, indexed by signal number. os_handler() can't take a
+// lock, so they are stored as the address of the function (0 for none) and whether it was
+// installed with SA_SIGINFO.
+static CHAINED: [AtomicUsize; MAX_SIGNALS] = [const { AtomicUsize::new(0) }; MAX_SIGNALS];
+static CHAINED_SIGINFO: [AtomicBool; MAX_SIGNALS] = [const { AtomicBool::new(false) }; MAX_SIGNALS];
+
+// Signals that did not fit into the pipe, indexed by signal number. The reader takes them
+// once it read the signals that were in the pipe before them. Until then, os_handler() adds
+// the signals received afterwards here as well, so that they are not read first. Only counts
+// are kept, so the overflowed signals are taken by signal number, not in arrival order. An eventfd
+// would only count the signals, not tell which ones arrived or who sent them, so Linux uses
+// the pipe and these counters as well.
+static OVERFLOW: [AtomicUsize; MAX_SIGNALS] = [const { AtomicUsize::new(0) }; MAX_SIGNALS];
+
+// The sum of OVERFLOW. It is counted before the signal is added to OVERFLOW.
+static OVERFLOW_TOTAL: AtomicUsize = AtomicUsize::new(0);
+
+// Signals os_handler() drops on arrival, indexed by signal number, counting the calls to
+// ignore() that were not undone yet.
+static IGNORED: [AtomicUsize; MAX_SIGNALS] = [const { AtomicUsize::new(0) }; MAX_SIGNALS];
+
+// Counters returned by stats().
+static RECEIVED: AtomicUsize = AtomicUsize::new(0);
+static DELIVERED: AtomicUsize = AtomicUsize::new(0);
+static OVERFLOWED: AtomicUsize = AtomicUsize::new(0);
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn os_handler(sig: c_int, info: *mut siginfo_t, context: *mut c_void) {
+    // write() and the chained handler may change errno under the code that was interrupted.
+    let errno = unsafe { errno_location() };
+    let saved = unsafe { *errno };
+    handle(sig, info, context);
+    unsafe { *errno = saved };
+}
+
+/// The errno of the calling thread.
+unsafe fn errno_location() -> *mut c_int {
+    #[cfg(any(target_os = "linux", target_os = "emscripten", target_os = "redox"))]
+    return libc::__errno_location();
+    #[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+    return libc::__errno();
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "dragonfly"
+    ))]
+    return libc::__error();
+    #[cfg(any(target_os = "solaris", target_os = "illumos"))]
+    return libc::___errno();
+}
+
+fn handle(sig: c_int, info: *mut siginfo_t, context: *mut c_void) {
+    let index = sig as usize;
+
+    if index < MAX_SIGNALS && IGNORED[index].load(Ordering::SeqCst) > 0 {
+        RECEIVED.fetch_add(1, Ordering::SeqCst);
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    } else {
+        unsafe { queue(sig, info) };
+    }
+
+    if index >= MAX_SIGNALS {
+        return;
+    }
+
+    let chained = CHAINED[index].load(Ordering::SeqCst);
+    if chained == 0 {
+        return;
+    }
+
+    unsafe {
+        if CHAINED_SIGINFO[index].load(Ordering::SeqCst) {
+            let chained: extern "C" fn(c_int, *mut siginfo_t, *mut c_void) =
+                mem::transmute(chained);
+            chained(sig, info, context);
+        } else {
+            let chained: extern "C" fn(c_int) = mem::transmute(chained);
+            chained(sig);
+        }
+    }
+}
+
+/// Queue a signal for the reader, called from os_handler().
+unsafe fn queue(sig: c_int, info: *mut siginfo_t) {
+    let index = sig as usize;
+
+    let message = match info.as_ref() {
+        Some(info) => encode(sig, info.si_code, info.si_pid(), info.si_uid()),
+        None => encode(sig, 0, 0, 0),
+    };
+    // Counted before writing, so that a reader never sees the message before the count.
+    RECEIVED.fetch_add(1, Ordering::SeqCst);
+    PENDING.fetch_add(1, Ordering::SeqCst);
+
+    // Queue behind the signals that overflowed earlier, if they were not all taken yet.
+    let behind_overflow = index < MAX_SIGNALS
+        && OVERFLOW_TOTAL
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                if n > 0 {
+                    Some(n + 1)
+                } else {
+                    None
+                }
+            })
+            .is_ok();
+
+    if behind_overflow {
+        overflow(index);
+    } else {
+        QUEUED.fetch_add(1, Ordering::SeqCst);
+        match unistd::write(PIPE.1, &message) {
+            Ok(_) => {}
+            Err(nix::Error::Sys(nix::errno::Errno::EAGAIN)) if index < MAX_SIGNALS => {
+                QUEUED.fetch_sub(1, Ordering::SeqCst);
+                OVERFLOW_TOTAL.fetch_add(1, Ordering::SeqCst);
+                overflow(index);
+            }
+            Err(_) => {
+                QUEUED.fetch_sub(1, Ordering::SeqCst);
+                PENDING.fetch_sub(1, Ordering::SeqCst);
+                DROPPED.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+/// Add a signal that can't be written to the pipe to OVERFLOW, after counting it in
+/// OVERFLOW_TOTAL.
+fn overflow(index: usize) {
+    OVERFLOWED.fetch_add(1, Ordering::SeqCst);
+    OVERFLOW[index].fetch_add(1, Ordering::SeqCst);
+}
+
+/// Remember the handler of `old` so that os_handler() calls it. Default and ignore dispositions
+/// are not chained to, the signal is handled by us in that case.
+fn chain(platform_signal: Signal, old: &nix::sys::signal::SigAction) {
+    let index = platform_signal as usize;
+    let (chained, siginfo) = match old.handler() {
+        SigHandler::SigDfl | SigHandler::SigIgn => (0, false),
+        SigHandler::Handler(f) => (f as usize, false),
+        SigHandler::SigAction(f) => (f as usize, true),
+    };
+    CHAINED_SIGINFO[index].store(siginfo, Ordering::SeqCst);
+    CHAINED[index].store(chained, Ordering::SeqCst);
+}
+
+/// Forget the handler os_handler() chains to.
+fn unchain(platform_signal: Signal) {
+    CHAINED[platform_signal as usize].store(0, Ordering::SeqCst);
+}
+
+/// Register os signal handler for the given signals.
+///
+/// With `restart`, system calls interrupted by the signals are restarted (`SA_RESTART`).
+///
+/// Must be called before calling [`block_ctrl_c()`](fn.block_ctrl_c.html)
+/// and should only be called once.
+///
+/// # Errors
+/// Will return an error if one of the signals can't be handled, if there already is a handler
+/// for one of them in [`InstallMode::Strict`] or if a system error occurred.
+///
+#[inline]
+pub unsafe fn init_os_handler(
+    signals: &[SignalType],
+    mode: InstallMode,
+    restart: bool,
+) -> Result<(), CtrlcError> {
+    use self::nix::fcntl;
+    use self::nix::sys::signal;
+
+    let platform_signals = platform_signals(signals)?;
+    if mode == InstallMode::Strict {
+        check_default(&platform_signals)?;
+    }
+
+    PIPE = pipe2(fcntl::OFlag::O_CLOEXEC)?;
+
+    let close_pipe = |e: CtrlcError| -> CtrlcError {
+        // Try to close the pipes. close() should not fail,
+        // but if it does, there isn't much we can do
+        let _ = unistd::close(PIPE.1);
+        let _ = unistd::close(PIPE.0);
+        e
+    };
+
+    // Make sure we never block on write in the os handler.
+    if let Err(e) = fcntl::fcntl(PIPE.1, fcntl::FcntlArg::F_SETFL(fcntl::OFlag::O_NONBLOCK)) {
+        return Err(close_pipe(e.into()));
+    }
+
+    let mut flags = signal::SaFlags::SA_SIGINFO;
+    if restart {
+        flags |= signal::SaFlags::SA_RESTART;
+    }
+    let handler = signal::SigHandler::SigAction(os_handler);
+    let new_action = signal::SigAction::new(handler, flags, signal::SigSet::empty());
+
+    let mut old_actions = Vec::with_capacity(platform_signals.len());
+    for &platform_signal in &platform_signals {
+        let result = match signal::sigaction(platform_signal, &new_action) {
+            Ok(old) => {
+                old_actions.push((platform_signal, old));
+                if mode == InstallMode::Chain {
+                    chain(platform_signal, &old);
+                }
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        };
+
+        if let Err(e) = result {
+            // Roll back the signals we already installed a handler for.
+            for (platform_signal, old) in old_actions.into_iter().rev() {
+                signal::sigaction(platform_signal, &old).unwrap();
+                unchain(platform_signal);
+            }
+            return Err(close_pipe(e));
+        }
+    }
+
+    *OLD_ACTIONS.lock().unwrap() = old_actions;
+
+    Ok(())
+}
+
+/// Restore the signal dispositions replaced by [`init_os_handler()`](fn.init_os_handler.html)
+/// and release the resources it allocated.
+///
+/// Must not be called while [`block_ctrl_c()`](fn.block_ctrl_c.html) is running.
+///
+#[inline]
+pub unsafe fn deinit_os_handler() {
+    use self::nix::sys::signal;
+
+    let old_actions = std::mem::take(&mut *OLD_ACTIONS.lock().unwrap());
+    for (platform_signal, old) in old_actions.into_iter().rev() {
+        // Nothing sensible to do if this fails, the handler keeps writing to a closed pipe.
+        let _ = signal::sigaction(platform_signal, &old);
+        unchain(platform_signal);
+    }
+
+    let _ = unistd::close(PIPE.1);
+    let _ = unistd::close(PIPE.0);
+    PIPE = (-1, -1);
+
+    // Signals that were not read are gone with the pipe.
+    for overflow in OVERFLOW.iter() {
+        overflow.store(0, Ordering::SeqCst);
+    }
+    OVERFLOW_TOTAL.store(0, Ordering::SeqCst);
+    QUEUED.store(0, Ordering::SeqCst);
+    DROPPED.fetch_add(PENDING.swap(0, Ordering::SeqCst), Ordering::SeqCst);
+}
+
+/// Undo the changes [`init_os_handler()`](fn.init_os_handler.html) made to the calling thread.
+///
+/// There are none with this backend.
+#[inline]
+pub unsafe fn deinit_thread() {}
+
+/// Makes a pending or the next call to [`block_ctrl_c()`](fn.block_ctrl_c.html) return `None`.
+#[inline]
+pub unsafe fn unblock_ctrl_c() {
+    // Zero is not a valid signal number, so it can't be mistaken for one.
+    let _ = unistd::write(PIPE.1, &[0u8; MESSAGE_SIZE]);
+}
+
+/// Blocks until a Ctrl-C signal is received and returns the signal that fired, or `None` if
+/// woken up by [`unblock_ctrl_c()`](fn.unblock_ctrl_c.html).
+///
+/// Must be called after calling [`init_os_handler()`](fn.init_os_handler.html).
+///
+/// # Errors
+/// Will return an error if a system error occurred.
+///
+#[inline]
+pub unsafe fn block_ctrl_c() -> Result<Option<SignalInfo>, CtrlcError> {
+    use std::io;
+    let mut buf = [0u8; MESSAGE_SIZE];
+
+    if let Some(info) = take_overflow() {
+        return Ok(Some(info));
+    }
+
+    // TODO: Can we safely convert the pipe fd into a std::io::Read
+    // with std::os::unix::io::FromRawFd, this would handle EINTR
+    // and everything for us.
+    loop {
+        match unistd::read(PIPE.0, &mut buf[..]) {
+            Ok(MESSAGE_SIZE) => break,
+            Ok(_) => return Err(CtrlcError::System(io::ErrorKind::UnexpectedEof.into())),
+            Err(nix::Error::Sys(nix::errno::Errno::EINTR)) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(dequeued(decode(&buf)?))
+}
+
+/// Returns the next signal that was received without blocking, or `None` if there is none.
+///
+/// Must be called after calling [`set_nonblocking()`](fn.set_nonblocking.html).
+///
+/// # Errors
+/// Will return an error if a system error occurred.
+///
+#[inline]
+pub unsafe fn poll_ctrl_c() -> Result<Option<SignalInfo>, CtrlcError> {
+    use std::io;
+    let mut buf = [0u8; MESSAGE_SIZE];
+
+    if let Some(info) = take_overflow() {
+        return Ok(Some(info));
+    }
+
+    loop {
+        match unistd::read(PIPE.0, &mut buf[..]) {
+            Ok(MESSAGE_SIZE) => {
+                // Skip wake-ups, nobody is blocking on the pipe.
+                if let Some(signal) = dequeued(decode(&buf)?) {
+                    return Ok(Some(signal));
+                }
+            }
+            Ok(_) => return Err(CtrlcError::System(io::ErrorKind::UnexpectedEof.into())),
+            Err(nix::Error::Sys(nix::errno::Errno::EINTR)) => {}
+            Err(nix::Error::Sys(nix::errno::Errno::EAGAIN)) => return Ok(take_overflow()),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Make reading signals nonblocking, for use with [`poll_ctrl_c()`](fn.poll_ctrl_c.html)
+/// instead of [`block_ctrl_c()`](fn.block_ctrl_c.html).
+///
+/// Must be called after calling [`init_os_handler()`](fn.init_os_handler.html).
+///
+/// # Errors
+/// Will return an error if a system error occurred.
+///
+#[inline]
+pub unsafe fn set_nonblocking() -> Result<(), CtrlcError> {
+    use self::nix::fcntl;
+
+    fcntl::fcntl(PIPE.0, fcntl::FcntlArg::F_SETFL(fcntl::OFlag::O_NONBLOCK))?;
+    Ok(())
+}
+
+/// Drop `signal` on arrival, until [`unignore()`](fn.unignore.html) was called as often as
+/// this.
+///
+/// # Errors
+/// Will return an error if the signal can't be handled.
+///
+#[inline]
+pub fn ignore(signal: &SignalType) -> Result<(), CtrlcError> {
+    let index = platform_signal(signal)? as usize;
+    if index < MAX_SIGNALS {
+        IGNORED[index].fetch_add(1, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+/// Undo a call to [`ignore()`](fn.ignore.html).
+#[inline]
+pub fn unignore(signal: &SignalType) {
+    if let Ok(platform_signal) = platform_signal(signal) {
+        let index = platform_signal as usize;
+        if index < MAX_SIGNALS {
+            IGNORED[index].fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// The number of received signals that were not returned by
+/// [`block_ctrl_c()`](fn.block_ctrl_c.html) or [`poll_ctrl_c()`](fn.poll_ctrl_c.html) yet.
+#[inline]
+pub fn pending_count() -> usize {
+    PENDING.load(Ordering::SeqCst)
+}
+
+/// Counters of the signals handled since the program started.
+#[inline]
+pub fn stats() -> Stats {
+    Stats {
+        received: RECEIVED.load(Ordering::SeqCst) as u64,
+        delivered: DELIVERED.load(Ordering::SeqCst) as u64,
+        overflowed: OVERFLOWED.load(Ordering::SeqCst) as u64,
+        dropped: DROPPED.load(Ordering::SeqCst) as u64,
+    }
+}
+
+/// The file descriptor that becomes readable when a signal is received.
+///
+/// Must be called after calling [`init_os_handler()`](fn.init_os_handler.html).
+///
+#[inline]
+pub unsafe fn read_fd() -> RawFd {
+    PIPE.0
+}
+
+/// Take a signal that was read off the pending count.
+fn received(info: Option<SignalInfo>) -> Option<SignalInfo> {
+    if info.is_some() {
+        PENDING.fetch_sub(1, Ordering::SeqCst);
+        DELIVERED.fetch_add(1, Ordering::SeqCst);
+    }
+    info
+}
+
+/// Take a signal that was read from the pipe off the pending counts.
+fn dequeued(info: Option<SignalInfo>) -> Option<SignalInfo> {
+    if info.is_some() {
+        QUEUED.fetch_sub(1, Ordering::SeqCst);
+    }
+    received(info)
+}
+
+/// Take a signal that did not fit into the pipe, if there is one and the pipe holds no older
+/// signals. The lowest signal number goes first, and its details are lost.
+fn take_overflow() -> Option<SignalInfo> {
+    if QUEUED.load(Ordering::SeqCst) > 0 {
+        return None;
+    }
+    OVERFLOW_TOTAL
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .ok()?;
+
+    // os_handler() may still be about to add the counted signal.
+    loop {
+        for (sig, overflow) in OVERFLOW.iter().enumerate() {
+            if overflow.load(Ordering::SeqCst) == 0 {
+                continue;
+            }
+            let taken = overflow
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if taken {
+                let info = Signal::try_from(sig as c_int)
+                    .ok()
+                    .map(|signal| SignalInfo::from(signal_type(signal)));
+                return received(info);
+            }
+        }
+        hint::spin_loop();
+    }
+}
+
+/// Build the message os_handler() writes for a signal.
+fn encode(sig: c_int, code: c_int, pid: i32, uid: u32) -> [u8; MESSAGE_SIZE] {
+    let mut message = [0u8; MESSAGE_SIZE];
+    message[0..4].copy_from_slice(&sig.to_ne_bytes());
+    message[4..8].copy_from_slice(&code.to_ne_bytes());
+    message[8..12].copy_from_slice(&pid.to_ne_bytes());
+    message[12..16].copy_from_slice(&uid.to_ne_bytes());
+    message
+}
+
+/// Decode a message written by os_handler(), `None` being the one of unblock_ctrl_c().
+fn decode(message: &[u8; MESSAGE_SIZE]) -> Result<Option<SignalInfo>, CtrlcError> {
+    let field = |offset: usize| {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&message[offset..offset + 4]);
+        bytes
+    };
+
+    let sig = c_int::from_ne_bytes(field(0));
+    if sig == 0 {
+        return Ok(None);
+    }
+
+    let signal = Signal::try_from(sig)?;
+    let code = c_int::from_ne_bytes(field(4));
+    let pid = i32::from_ne_bytes(field(8));
+    let uid = u32::from_ne_bytes(field(12));
+
+    // si_pid is zero for signals the kernel generated on its own, e.g. for the terminal.
+    let sent = pid > 0;
+    Ok(Some(SignalInfo {
+        signal: signal_type(signal),
+        pid: if sent { Some(pid as u32) } else { None },
+        uid: if sent { Some(uid) } else { None },
+        code: Some(code),
+    }))
+}
@@ -0,0 +1,427 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+// Instead of installing a signal handler, the signals are blocked and read from a signalfd(2).
+// Nothing runs in signal handler context, and the whole signalfd_siginfo is available.
+//
+// Blocking signals only affects the calling thread and the threads it spawns afterwards. A
+// signal delivered to a thread that does not block it gets its default disposition.
+
+use super::nix::libc::{self, c_int, signalfd_siginfo};
+use super::nix::poll::{poll, PollFd, PollFlags};
+use super::nix::sys::signal::{self, SaFlags, SigAction, SigHandler, SigSet, SigmaskHow};
+use super::nix::sys::signalfd::{signalfd, SfdFlags};
+use super::nix::{errno::Errno, fcntl, unistd};
+use super::{check_default, nix, pipe2, platform_signal, platform_signals, signal_type, Signal};
+use error::Error as CtrlcError;
+use std::convert::TryFrom;
+use std::io;
+use std::mem;
+use std::os::unix::io::RawFd;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+use InstallMode;
+use SignalInfo;
+use SignalType;
+use Stats;
+
+static mut SIGNAL_FD: RawFd = -1;
+
+// Written to by unblock_ctrl_c(), a signalfd can't be woken up otherwise.
+static mut WAKE_PIPE: (RawFd, RawFd) = (-1, -1);
+
+// Signal dispositions replaced by init_os_handler(), restored by deinit_os_handler().
+static OLD_ACTIONS: Mutex<Vec<(Signal, SigAction)>> = Mutex::new(Vec::new());
+
+// The thread that called init_os_handler() and the signals it blocked there, unblocked again by
+// deinit_thread().
+static BLOCKED: Mutex<Option<(ThreadId, SigSet)>> = Mutex::new(None);
+
+// Larger than the number of signals on any supported platform.
+const MAX_SIGNALS: usize = 128;
+
+// Signals that are dropped when read, indexed by signal number, counting the calls to ignore()
+// that were not undone yet. Only changed with IGNORE_LOCK held, see unignore().
+static IGNORED: [AtomicUsize; MAX_SIGNALS] = [const { AtomicUsize::new(0) }; MAX_SIGNALS];
+static IGNORE_LOCK: Mutex<()> = Mutex::new(());
+
+// Counters returned by stats(). Signals are only seen when they are read from the signalfd.
+static DELIVERED: AtomicUsize = AtomicUsize::new(0);
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+/// Register os signal handler for the given signals.
+///
+/// Blocks the signals in the calling thread, so it must be called before spawning threads
+/// that should not receive them. `restart` has no effect, blocked signals don't interrupt
+/// system calls. Must be called before calling [`block_ctrl_c()`](fn.block_ctrl_c.html) and
+/// should only be called once.
+///
+/// # Errors
+/// Will return an error if one of the signals can't be handled, if there already is a handler
+/// for one of them in [`InstallMode::Strict`], in [`InstallMode::Chain`], which this backend
+/// does not support, or if a system error occurred.
+///
+#[inline]
+pub unsafe fn init_os_handler(
+    signals: &[SignalType],
+    mode: InstallMode,
+    _: bool,
+) -> Result<(), CtrlcError> {
+    if mode == InstallMode::Chain {
+        return Err(CtrlcError::System(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the signalfd backend can't chain to other signal handlers",
+        )));
+    }
+
+    let platform_signals = platform_signals(signals)?;
+    if mode == InstallMode::Strict {
+        check_default(&platform_signals)?;
+    }
+    let mut mask = SigSet::empty();
+    for &platform_signal in &platform_signals {
+        mask.add(platform_signal);
+    }
+
+    WAKE_PIPE = pipe2(fcntl::OFlag::O_CLOEXEC)?;
+
+    let close_pipe = |e: CtrlcError| -> CtrlcError {
+        // Try to close the pipes. close() should not fail,
+        // but if it does, there isn't much we can do
+        let _ = unistd::close(WAKE_PIPE.1);
+        let _ = unistd::close(WAKE_PIPE.0);
+        e
+    };
+
+    // Make sure unblock_ctrl_c() never blocks.
+    if let Err(e) = fcntl::fcntl(
+        WAKE_PIPE.1,
+        fcntl::FcntlArg::F_SETFL(fcntl::OFlag::O_NONBLOCK),
+    ) {
+        return Err(close_pipe(e.into()));
+    }
+
+    // Ignored signals are discarded instead of being queued for the signalfd.
+    let default = SigAction::new(SigHandler::SigDfl, SaFlags::empty(), SigSet::empty());
+
+    let mut old_actions = Vec::with_capacity(platform_signals.len());
+    for &platform_signal in &platform_signals {
+        let result = match signal::sigaction(platform_signal, &default) {
+            Ok(old) => {
+                old_actions.push((platform_signal, old));
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        };
+
+        if let Err(e) = result {
+            // Roll back the signals we already changed the disposition of.
+            for (platform_signal, old) in old_actions.into_iter().rev() {
+                signal::sigaction(platform_signal, &old).unwrap();
+            }
+            return Err(close_pipe(e));
+        }
+    }
+
+    let result = mask
+        .thread_swap_mask(SigmaskHow::SIG_BLOCK)
+        .map_err(CtrlcError::from)
+        .and_then(|old_mask| {
+            match signalfd(-1, &mask, SfdFlags::SFD_CLOEXEC | SfdFlags::SFD_NONBLOCK) {
+                Ok(fd) => Ok((fd, old_mask)),
+                Err(e) => {
+                    let _ = old_mask.thread_set_mask();
+                    Err(e.into())
+                }
+            }
+        });
+
+    match result {
+        Ok((fd, old_mask)) => {
+            SIGNAL_FD = fd;
+
+            // Signals that were blocked before stay blocked.
+            let mut blocked = SigSet::empty();
+            for &platform_signal in &platform_signals {
+                if !old_mask.contains(platform_signal) {
+                    blocked.add(platform_signal);
+                }
+            }
+            *BLOCKED.lock().unwrap() = Some((thread::current().id(), blocked));
+        }
+        Err(e) => {
+            for (platform_signal, old) in old_actions.into_iter().rev() {
+                signal::sigaction(platform_signal, &old).unwrap();
+            }
+            return Err(close_pipe(e));
+        }
+    }
+
+    *OLD_ACTIONS.lock().unwrap() = old_actions;
+
+    Ok(())
+}
+
+/// Restore the signal dispositions replaced by [`init_os_handler()`](fn.init_os_handler.html)
+/// and release the resources it allocated.
+///
+/// The signals stay blocked until [`deinit_thread()`](fn.deinit_thread.html) is called.
+/// Signals that were received but not read yet are discarded.
+///
+/// Must not be called while [`block_ctrl_c()`](fn.block_ctrl_c.html) is running.
+///
+#[inline]
+pub unsafe fn deinit_os_handler() {
+    // Discard pending signals, so that they don't get the default disposition once unblocked.
+    while let Ok(Some(_)) = read_signal() {
+        DELIVERED.fetch_sub(1, Ordering::SeqCst);
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let old_actions = mem::take(&mut *OLD_ACTIONS.lock().unwrap());
+    for (platform_signal, old) in old_actions.into_iter().rev() {
+        let _ = signal::sigaction(platform_signal, &old);
+    }
+
+    let _ = unistd::close(SIGNAL_FD);
+    SIGNAL_FD = -1;
+
+    let _ = unistd::close(WAKE_PIPE.1);
+    let _ = unistd::close(WAKE_PIPE.0);
+    WAKE_PIPE = (-1, -1);
+}
+
+/// Unblock the signals [`init_os_handler()`](fn.init_os_handler.html) blocked, if called from
+/// the thread that called it and after
+/// [`deinit_os_handler()`](fn.deinit_os_handler.html).
+///
+/// Threads spawned in the meantime inherited the blocked signals and keep them blocked.
+#[inline]
+pub unsafe fn deinit_thread() {
+    if SIGNAL_FD != -1 {
+        return;
+    }
+
+    let mut blocked = BLOCKED.lock().unwrap();
+    let same_thread = blocked
+        .as_ref()
+        .is_some_and(|&(id, _)| id == thread::current().id());
+    if same_thread {
+        if let Some((_, set)) = blocked.take() {
+            let _ = set.thread_unblock();
+        }
+    }
+}
+
+/// Makes a pending or the next call to [`block_ctrl_c()`](fn.block_ctrl_c.html) return `None`.
+#[inline]
+pub unsafe fn unblock_ctrl_c() {
+    let _ = unistd::write(WAKE_PIPE.1, &[0u8]);
+}
+
+/// Blocks until a Ctrl-C signal is received and returns the signal that fired, or `None` if
+/// woken up by [`unblock_ctrl_c()`](fn.unblock_ctrl_c.html).
+///
+/// Signals that were received before the wake-up are returned first.
+///
+/// Must be called after calling [`init_os_handler()`](fn.init_os_handler.html).
+///
+/// # Errors
+/// Will return an error if a system error occurred.
+///
+#[inline]
+pub unsafe fn block_ctrl_c() -> Result<Option<SignalInfo>, CtrlcError> {
+    loop {
+        let mut fds = [
+            PollFd::new(SIGNAL_FD, PollFlags::POLLIN),
+            PollFd::new(WAKE_PIPE.0, PollFlags::POLLIN),
+        ];
+        match poll(&mut fds, -1) {
+            Ok(_) => {}
+            Err(nix::Error::Sys(Errno::EINTR)) => continue,
+            Err(e) => return Err(e.into()),
+        }
+
+        if let Some(info) = read_signal()? {
+            return Ok(Some(info));
+        }
+
+        let woken = fds[1].revents().is_some_and(|events| !events.is_empty());
+        if woken {
+            let mut buf = [0u8];
+            return match unistd::read(WAKE_PIPE.0, &mut buf[..]) {
+                Ok(1) => Ok(None),
+                Ok(_) => Err(CtrlcError::System(io::ErrorKind::UnexpectedEof.into())),
+                Err(e) => Err(e.into()),
+            };
+        }
+    }
+}
+
+/// Returns the next signal that was received without blocking, or `None` if there is none.
+///
+/// Must be called after calling [`set_nonblocking()`](fn.set_nonblocking.html).
+///
+/// # Errors
+/// Will return an error if a system error occurred.
+///
+#[inline]
+pub unsafe fn poll_ctrl_c() -> Result<Option<SignalInfo>, CtrlcError> {
+    read_signal()
+}
+
+/// Make reading signals nonblocking, for use with [`poll_ctrl_c()`](fn.poll_ctrl_c.html)
+/// instead of [`block_ctrl_c()`](fn.block_ctrl_c.html).
+///
+/// The signalfd is always nonblocking, so there is nothing to do.
+///
+/// # Errors
+/// Never fails, the `Result` is for parity with the self-pipe backend.
+///
+#[inline]
+pub unsafe fn set_nonblocking() -> Result<(), CtrlcError> {
+    Ok(())
+}
+
+/// Drop `signal` until [`unignore()`](fn.unignore.html) was called as often as this.
+///
+/// # Errors
+/// Will return an error if the signal can't be handled.
+///
+#[inline]
+pub fn ignore(signal: &SignalType) -> Result<(), CtrlcError> {
+    let index = platform_signal(signal)? as usize;
+    if index < MAX_SIGNALS {
+        let _lock = IGNORE_LOCK.lock().unwrap();
+        IGNORED[index].fetch_add(1, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+/// Undo a call to [`ignore()`](fn.ignore.html).
+///
+/// A signal can't be told when it arrived, only when it is read. So that the ones that arrived
+/// while ignored are not read afterwards, the pending ones are discarded by setting the
+/// disposition to `SIG_IGN` for a moment, which discards them even though they are blocked.
+#[inline]
+pub fn unignore(signal: &SignalType) {
+    let platform_signal = match platform_signal(signal) {
+        Ok(platform_signal) => platform_signal,
+        Err(_) => return,
+    };
+    let index = platform_signal as usize;
+    if index >= MAX_SIGNALS {
+        return;
+    }
+
+    // Signals read in the meantime are still dropped by read_signal().
+    let _lock = IGNORE_LOCK.lock().unwrap();
+    let handled = OLD_ACTIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|&(s, _)| s == platform_signal);
+    if handled && IGNORED[index].load(Ordering::SeqCst) == 1 {
+        let ignore = SigAction::new(SigHandler::SigIgn, SaFlags::empty(), SigSet::empty());
+        unsafe {
+            if let Ok(old) = signal::sigaction(platform_signal, &ignore) {
+                let _ = signal::sigaction(platform_signal, &old);
+            }
+        }
+    }
+    IGNORED[index].fetch_sub(1, Ordering::SeqCst);
+}
+
+/// The number of received signals that were not returned by
+/// [`block_ctrl_c()`](fn.block_ctrl_c.html) or [`poll_ctrl_c()`](fn.poll_ctrl_c.html) yet.
+///
+/// Pending signals of the same kind are merged, so this is the number of different signals
+/// that are pending.
+#[inline]
+pub fn pending_count() -> usize {
+    let mut pending = mem::MaybeUninit::<libc::sigset_t>::uninit();
+    let pending = unsafe {
+        if libc::sigpending(pending.as_mut_ptr()) != 0 {
+            return 0;
+        }
+        pending.assume_init()
+    };
+
+    OLD_ACTIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|&&(platform_signal, _)| unsafe {
+            libc::sigismember(&pending, platform_signal as c_int) == 1
+        })
+        .count()
+}
+
+/// Counters of the signals handled since the program started.
+///
+/// Signals of the same kind that are pending at the same time are merged by the kernel, they
+/// are counted once.
+#[inline]
+pub fn stats() -> Stats {
+    let delivered = DELIVERED.load(Ordering::SeqCst) as u64;
+    let dropped = DROPPED.load(Ordering::SeqCst) as u64;
+    Stats {
+        received: delivered + dropped,
+        delivered,
+        overflowed: 0,
+        dropped,
+    }
+}
+
+/// The file descriptor that becomes readable when a signal is received.
+///
+/// Must be called after calling [`init_os_handler()`](fn.init_os_handler.html).
+///
+#[inline]
+pub unsafe fn read_fd() -> RawFd {
+    SIGNAL_FD
+}
+
+/// Read the next signal from the signalfd that is not ignored, `None` if there is none.
+unsafe fn read_signal() -> Result<Option<SignalInfo>, CtrlcError> {
+    let mut buf = [0u8; mem::size_of::<signalfd_siginfo>()];
+
+    let _lock = IGNORE_LOCK.lock().unwrap();
+    let info = loop {
+        match unistd::read(SIGNAL_FD, &mut buf[..]) {
+            Ok(n) if n == buf.len() => {}
+            Ok(_) => return Err(CtrlcError::System(io::ErrorKind::UnexpectedEof.into())),
+            Err(nix::Error::Sys(Errno::EINTR)) => continue,
+            Err(nix::Error::Sys(Errno::EAGAIN)) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+
+        let info: signalfd_siginfo = ptr::read_unaligned(buf.as_ptr() as *const signalfd_siginfo);
+        let index = info.ssi_signo as usize;
+        if index < MAX_SIGNALS && IGNORED[index].load(Ordering::SeqCst) > 0 {
+            DROPPED.fetch_add(1, Ordering::SeqCst);
+            continue;
+        }
+        break info;
+    };
+
+    DELIVERED.fetch_add(1, Ordering::SeqCst);
+    let signal = Signal::try_from(info.ssi_signo as c_int)?;
+
+    // ssi_pid is zero for signals the kernel generated on its own, e.g. for the terminal.
+    let sent = info.ssi_pid > 0;
+    Ok(Some(SignalInfo {
+        signal: signal_type(signal),
+        pid: if sent { Some(info.ssi_pid) } else { None },
+        uid: if sent { Some(info.ssi_uid) } else { None },
+        code: Some(info.ssi_code),
+    }))
+}
@@ -12,12 +12,26 @@ extern crate winapi;
 use self::winapi::ctypes::c_long;
 use self::winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
 use self::winapi::shared::ntdef::HANDLE;
+use self::winapi::shared::ntstatus::STATUS_CONTROL_C_EXIT;
+use self::winapi::shared::winerror::WAIT_TIMEOUT;
 use self::winapi::um::consoleapi::SetConsoleCtrlHandler;
 use self::winapi::um::handleapi::CloseHandle;
+use self::winapi::um::processthreadsapi::ExitProcess;
 use self::winapi::um::synchapi::{ReleaseSemaphore, WaitForSingleObject};
 use self::winapi::um::winbase::{CreateSemaphoreA, INFINITE, WAIT_FAILED, WAIT_OBJECT_0};
+use self::winapi::um::wincon::{
+    CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT, CTRL_C_EVENT, CTRL_LOGOFF_EVENT, CTRL_SHUTDOWN_EVENT,
+};
+use error::Error as CtrlcError;
+use std::collections::VecDeque;
 use std::io;
 use std::ptr;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use InstallMode;
+use SignalInfo;
+use SignalType;
+use Stats;
 
 /// Platform specific error type
 pub type Error = io::Error;
@@ -28,38 +42,176 @@ pub type Signal = DWORD;
 const MAX_SEM_COUNT: c_long = 255;
 static mut SEMAPHORE: HANDLE = 0 as HANDLE;
 
-unsafe extern "system" fn os_handler(_: DWORD) -> BOOL {
+// Bit mask of the console control events we were asked to handle.
+static EVENT_MASK: AtomicU32 = AtomicU32::new(0);
+
+// Handler routines run in their own thread, so unlike on Unix we are free to take a lock here.
+// `None` is queued by unblock_ctrl_c().
+static EVENTS: Mutex<VecDeque<Option<DWORD>>> = Mutex::new(VecDeque::new());
+
+// Events os_handler() drops on arrival, indexed by event, counting the calls to ignore() that
+// were not undone yet.
+static IGNORED: [AtomicUsize; 32] = [const { AtomicUsize::new(0) }; 32];
+
+// Counters returned by stats().
+static RECEIVED: AtomicUsize = AtomicUsize::new(0);
+static DELIVERED: AtomicUsize = AtomicUsize::new(0);
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe extern "system" fn os_handler(event: DWORD) -> BOOL {
+    if event >= 32 || EVENT_MASK.load(Ordering::SeqCst) & (1 << event) == 0 {
+        // Let the next handler routine deal with it.
+        return FALSE;
+    }
+
+    RECEIVED.fetch_add(1, Ordering::SeqCst);
+    if IGNORED[event as usize].load(Ordering::SeqCst) > 0 {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+        return TRUE;
+    }
+    if let Ok(mut events) = EVENTS.lock() {
+        events.push_back(Some(event));
+    }
     // Assuming this always succeeds. Can't really handle errors in any meaningful way.
     ReleaseSemaphore(SEMAPHORE, 1, ptr::null_mut());
     TRUE
 }
 
-/// Register os signal handler.
+/// Map a platform signal to its cross-platform representation.
+fn signal_type(signal: Signal) -> SignalType {
+    match signal {
+        CTRL_C_EVENT | CTRL_BREAK_EVENT => SignalType::Ctrlc,
+        CTRL_CLOSE_EVENT => SignalType::Termination,
+        other => SignalType::Other(other),
+    }
+}
+
+/// Map a cross-platform signal to the bit mask of the console control events it covers.
+fn event_mask(signal: &SignalType) -> Result<u32, CtrlcError> {
+    match *signal {
+        SignalType::Ctrlc => Ok(1 << CTRL_C_EVENT | 1 << CTRL_BREAK_EVENT),
+        SignalType::Termination => Ok(1 << CTRL_CLOSE_EVENT),
+        SignalType::Hangup | SignalType::Quit => Err(CtrlcError::NoSuchSignal(*signal)),
+        SignalType::Other(event) => match event {
+            CTRL_C_EVENT | CTRL_BREAK_EVENT | CTRL_CLOSE_EVENT | CTRL_LOGOFF_EVENT
+            | CTRL_SHUTDOWN_EVENT => Ok(1 << event),
+            _ => Err(CtrlcError::NoSuchSignal(*signal)),
+        },
+    }
+}
+
+/// Register os signal handler for the given signals.
 ///
 /// Must be called before calling [`block_ctrl_c()`](fn.block_ctrl_c.html)
 /// and should only be called once.
 ///
 /// # Errors
-/// Will return an error if a system error occurred.
+/// Will return an error if one of the signals can't be handled or if a system error occurred.
 ///
 #[inline]
-pub unsafe fn init_os_handler() -> Result<(), Error> {
+pub unsafe fn init_os_handler(
+    signals: &[SignalType],
+    _: InstallMode,
+    _: bool,
+) -> Result<(), CtrlcError> {
+    let mut mask = 0;
+    for signal in signals {
+        mask |= event_mask(signal)?;
+    }
+    EVENT_MASK.store(mask, Ordering::SeqCst);
+
     SEMAPHORE = CreateSemaphoreA(ptr::null_mut(), 0, MAX_SEM_COUNT, ptr::null());
     if SEMAPHORE.is_null() {
-        return Err(io::Error::last_os_error());
+        return Err(io::Error::last_os_error().into());
     }
 
     if SetConsoleCtrlHandler(Some(os_handler), TRUE) == FALSE {
         let e = io::Error::last_os_error();
         CloseHandle(SEMAPHORE);
         SEMAPHORE = 0 as HANDLE;
-        return Err(e);
+        return Err(e.into());
     }
 
     Ok(())
 }
 
-/// Blocks until a Ctrl-C signal is received.
+/// Unregister the os signal handler and release the resources
+/// [`init_os_handler()`](fn.init_os_handler.html) allocated.
+///
+/// Must not be called while [`block_ctrl_c()`](fn.block_ctrl_c.html) is running.
+///
+#[inline]
+pub unsafe fn deinit_os_handler() {
+    SetConsoleCtrlHandler(Some(os_handler), FALSE);
+    EVENT_MASK.store(0, Ordering::SeqCst);
+
+    CloseHandle(SEMAPHORE);
+    SEMAPHORE = 0 as HANDLE;
+
+    if let Ok(mut events) = EVENTS.lock() {
+        // Events that were not read are gone with the handler.
+        let unread = events.iter().filter(|event| event.is_some()).count();
+        DROPPED.fetch_add(unread, Ordering::SeqCst);
+        events.clear();
+    }
+}
+
+/// Drop the events of `signal` on arrival, until [`unignore()`](fn.unignore.html) was called
+/// as often as this.
+///
+/// # Errors
+/// Will return an error if the signal can't be handled.
+///
+#[inline]
+pub fn ignore(signal: &SignalType) -> Result<(), CtrlcError> {
+    let mask = event_mask(signal)?;
+    for (event, ignored) in IGNORED.iter().enumerate() {
+        if mask & (1 << event) != 0 {
+            ignored.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+    Ok(())
+}
+
+/// Undo a call to [`ignore()`](fn.ignore.html).
+#[inline]
+pub fn unignore(signal: &SignalType) {
+    if let Ok(mask) = event_mask(signal) {
+        for (event, ignored) in IGNORED.iter().enumerate() {
+            if mask & (1 << event) != 0 {
+                ignored.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+/// End the process the way an unhandled console control event does.
+///
+/// Never returns, the `Result` is for parity with Unix.
+///
+#[inline]
+pub unsafe fn raise_default(_: &SignalType) -> Result<(), CtrlcError> {
+    ExitProcess(STATUS_CONTROL_C_EXIT as u32);
+    Ok(())
+}
+
+/// Undo the changes [`init_os_handler()`](fn.init_os_handler.html) made to the calling thread.
+///
+/// There are none with this backend.
+#[inline]
+pub unsafe fn deinit_thread() {}
+
+/// Makes a pending or the next call to [`block_ctrl_c()`](fn.block_ctrl_c.html) return `None`.
+#[inline]
+pub unsafe fn unblock_ctrl_c() {
+    if let Ok(mut events) = EVENTS.lock() {
+        events.push_back(None);
+    }
+    ReleaseSemaphore(SEMAPHORE, 1, ptr::null_mut());
+}
+
+/// Blocks until a Ctrl-C signal is received and returns the signal that fired, or `None` if
+/// woken up by [`unblock_ctrl_c()`](fn.unblock_ctrl_c.html).
 ///
 /// Must be called after calling [`init_os_handler()`](fn.init_os_handler.html).
 ///
@@ -67,16 +219,76 @@ pub unsafe fn init_os_handler() -> Result<(), Error> {
 /// Will return an error if a system error occurred.
 ///
 #[inline]
-pub unsafe fn block_ctrl_c() -> Result<(), Error> {
+pub unsafe fn block_ctrl_c() -> Result<Option<SignalInfo>, Error> {
     match WaitForSingleObject(SEMAPHORE, INFINITE) {
-        WAIT_OBJECT_0 => Ok(()),
+        WAIT_OBJECT_0 => Ok(next_event()),
         WAIT_FAILED => Err(io::Error::last_os_error()),
-        ret => Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!(
-                "WaitForSingleObject(), unexpected return value \"{:x}\"",
-                ret
-            ),
-        )),
+        ret => Err(io::Error::other(format!(
+            "WaitForSingleObject(), unexpected return value \"{:x}\"",
+            ret
+        ))),
     }
 }
+
+/// Returns the next signal that was received without blocking, or `None` if there is none.
+///
+/// Must be called after calling [`init_os_handler()`](fn.init_os_handler.html).
+///
+/// # Errors
+/// Will return an error if a system error occurred.
+///
+#[inline]
+pub unsafe fn poll_ctrl_c() -> Result<Option<SignalInfo>, Error> {
+    loop {
+        match WaitForSingleObject(SEMAPHORE, 0) {
+            WAIT_OBJECT_0 => {
+                // Skip wake-ups, nobody is blocking on the semaphore.
+                if let Some(signal) = next_event() {
+                    return Ok(Some(signal));
+                }
+            }
+            WAIT_TIMEOUT => return Ok(None),
+            WAIT_FAILED => return Err(io::Error::last_os_error()),
+            ret => {
+                return Err(io::Error::other(format!(
+                    "WaitForSingleObject(), unexpected return value \"{:x}\"",
+                    ret
+                )))
+            }
+        }
+    }
+}
+
+/// The number of received signals that were not returned by
+/// [`block_ctrl_c()`](fn.block_ctrl_c.html) or [`poll_ctrl_c()`](fn.poll_ctrl_c.html) yet.
+#[inline]
+pub fn pending_count() -> usize {
+    match EVENTS.lock() {
+        Ok(events) => events.iter().filter(|event| event.is_some()).count(),
+        Err(_) => 0,
+    }
+}
+
+/// Counters of the signals handled since the program started.
+#[inline]
+pub fn stats() -> Stats {
+    Stats {
+        received: RECEIVED.load(Ordering::SeqCst) as u64,
+        delivered: DELIVERED.load(Ordering::SeqCst) as u64,
+        overflowed: 0,
+        dropped: DROPPED.load(Ordering::SeqCst) as u64,
+    }
+}
+
+/// Take the event the semaphore was released for off the queue.
+fn next_event() -> Option<SignalInfo> {
+    let event = match EVENTS.lock() {
+        Ok(mut events) => events.pop_front(),
+        Err(_) => None,
+    };
+    // The event queue can only be empty if os_handler() failed to take the lock,
+    // in which case we still know a Ctrl-C event arrived.
+    let event = event.unwrap_or(Some(CTRL_C_EVENT))?;
+    DELIVERED.fetch_add(1, Ordering::SeqCst);
+    Some(SignalInfo::from(signal_type(event)))
+}
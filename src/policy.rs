@@ -0,0 +1,151 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use platform;
+use std::collections::VecDeque;
+use std::process;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use SignalType;
+
+/// What the signal handling thread does when a signal is received, besides calling the handler.
+///
+/// Set with [set_policy()](fn.set_policy.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Policy {
+    /// Call the handler for every signal.
+    #[default]
+    Handle,
+    /// Exit the process with `code` when the `count`th signal is received, instead of calling
+    /// the handler for it. If `window` is set, only signals received within that duration of
+    /// each other count.
+    ///
+    /// `ForceExitAfter { count: 2, .. }` lets the first Ctrl-C request a graceful shutdown,
+    /// while the second one exits immediately.
+    ForceExitAfter {
+        /// Number of signals after which the process exits.
+        count: usize,
+        /// Exit code of the process.
+        code: i32,
+        /// Only count signals received within this duration.
+        window: Option<Duration>,
+    },
+    /// Call the handler, then end the process with
+    /// [exit_by_signal()](fn.exit_by_signal.html), so that the exit status of the process
+    /// tells that it was killed by the signal.
+    ExitBySignal,
+}
+
+struct State {
+    policy: Policy,
+    received: VecDeque<Instant>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    policy: Policy::Handle,
+    received: VecDeque::new(),
+});
+
+/// Set the policy of the signal handling thread.
+///
+/// Takes effect immediately, also for an already registered handler. Signals received before
+/// the policy was set don't count towards it.
+///
+/// # Example
+/// ```no_run
+/// use ctrlc::Policy;
+///
+/// // The second Ctrl-C exits the process, even if the handler is still busy.
+/// ctrlc::set_policy(Policy::ForceExitAfter {
+///     count: 2,
+///     code: 130,
+///     window: None,
+/// });
+/// ctrlc::set_handler(|| println!("Shutting down, press Ctrl-C again to force"))
+///     .expect("Error setting Ctrl-C handler");
+/// ```
+///
+pub fn set_policy(policy: Policy) {
+    let mut state = STATE.lock().unwrap();
+    state.policy = policy;
+    state.received.clear();
+}
+
+/// End the process by raising `signal` with its default disposition.
+///
+/// Shells and process supervisors can tell from the exit status whether a process was killed
+/// by a signal. A shell running a loop, for example, only stops the loop on Ctrl-C if the
+/// process was killed by `SIGINT`, but not if it exited normally after handling it. Call this
+/// at the end of the handler to end the process the way it would have without handler.
+///
+/// On Unix this restores the default disposition of the signal, unblocks it and raises it. On
+/// Windows the process exits with `STATUS_CONTROL_C_EXIT`. If the default disposition of the
+/// signal is not to end the process, the process exits with code `1`.
+///
+/// # Example
+/// ```no_run
+/// ctrlc::set_handler_with_signal(|signal| {
+///     println!("Cleaning up");
+///     ctrlc::exit_by_signal(signal);
+/// })
+/// .expect("Error setting Ctrl-C handler");
+/// ```
+///
+pub fn exit_by_signal(signal: SignalType) -> ! {
+    unsafe {
+        let _ = platform::raise_default(&signal);
+    }
+    process::exit(1);
+}
+
+/// Apply the policy to a received signal, before the handler is called.
+pub fn apply() {
+    let mut state = STATE.lock().unwrap();
+    let now = Instant::now();
+
+    if let Policy::ForceExitAfter {
+        count,
+        code,
+        window,
+    } = state.policy
+    {
+        if let Some(window) = window {
+            while state
+                .received
+                .front()
+                .is_some_and(|&t| now.duration_since(t) > window)
+            {
+                state.received.pop_front();
+            }
+        }
+
+        state.received.push_back(now);
+        if state.received.len() >= count {
+            process::exit(code);
+        }
+    }
+}
+
+/// Apply the policy to a received signal, after the handler was called.
+pub fn apply_after(signal: SignalType) {
+    let policy = STATE.lock().unwrap().policy;
+    if policy == Policy::ExitBySignal {
+        exit_by_signal(signal);
+    }
+}
+
+/// The policy set last.
+pub fn policy() -> Policy {
+    STATE.lock().unwrap().policy
+}
+
+/// Forget about the signals received so far.
+pub fn reset() {
+    STATE.lock().unwrap().received.clear();
+}
@@ -0,0 +1,66 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use error::Error;
+use handler;
+use platform;
+use SignalType;
+
+/// Register the OS signal handler for Ctrl-C without starting a signal handling thread.
+///
+/// Received signals are queued until they are taken with [try_recv()](fn.try_recv.html). Call
+/// this at the start of your program, signals that arrive before the handler is registered
+/// are not seen by `try_recv()`. The handler stays registered for the rest of the program and
+/// can't be combined with [set_handler()](fn.set_handler.html).
+///
+/// # Example
+/// ```no_run
+/// ctrlc::init_polling().expect("Error setting Ctrl-C handler");
+///
+/// println!("Waiting for Ctrl-C...");
+/// loop {
+///     if ctrlc::try_recv().expect("Error polling for Ctrl-C").is_some() {
+///         break;
+///     }
+///     std::thread::sleep(std::time::Duration::from_millis(10));
+/// }
+/// println!("Got it! Exiting...");
+/// ```
+///
+/// # Errors
+/// Will return an error if another `ctrlc::set_handler()` handler exists or if a
+/// system error occurred while setting the handler.
+///
+pub fn init_polling() -> Result<(), Error> {
+    handler::install_threadless()
+}
+
+/// Take the next received Ctrl-C signal without blocking, or `None` if there is none.
+///
+/// Registers the OS signal handler like [init_polling()](fn.init_polling.html) if that was
+/// not done yet.
+///
+/// # Errors
+/// Will return an error if another `ctrlc::set_handler()` handler exists or if a
+/// system error occurred while setting the handler or reading the signal.
+///
+pub fn try_recv() -> Result<Option<SignalType>, Error> {
+    handler::install_threadless()?;
+    let info = unsafe { platform::poll_ctrl_c()? };
+    Ok(info.map(|info| info.signal))
+}
+
+/// The number of received Ctrl-C signals that were not taken with
+/// [try_recv()](fn.try_recv.html) yet.
+///
+/// Returns `0` if no handler is registered.
+///
+pub fn pending_count() -> usize {
+    platform::pending_count()
+}
@@ -0,0 +1,273 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use error::Error;
+use handler;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+use subscriber::{self, SubscriptionId};
+
+struct Hook {
+    name: String,
+    priority: i32,
+    timeout: Option<Duration>,
+    dependencies: Vec<String>,
+    f: Box<dyn FnOnce() + Send>,
+}
+
+// By descending priority, the last registered first among hooks of equal priority. This is
+// the order the hooks run in, unless a hook depends on one that comes before it.
+static HOOKS: Mutex<Vec<Hook>> = Mutex::new(Vec::new());
+
+// The subscriber that runs the hooks, registered while there are hooks to run.
+static SUBSCRIPTION: Mutex<Option<SubscriptionId>> = Mutex::new(None);
+
+static SUMMARY: Mutex<Option<ShutdownSummary>> = Mutex::new(None);
+
+/// The outcome of running the shutdown hooks, by hook name in the order the hooks ran.
+///
+/// Returned by [run_shutdown_hooks()](fn.run_shutdown_hooks.html) and
+/// [shutdown_summary()](fn.shutdown_summary.html).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ShutdownSummary {
+    /// Hooks that returned.
+    pub finished: Vec<String>,
+    /// Hooks that panicked, or whose thread could not be spawned.
+    pub failed: Vec<String>,
+    /// Hooks that were still running when their timeout passed. They are left running in the
+    /// background.
+    pub timed_out: Vec<String>,
+}
+
+/// Register a hook that runs once when a Ctrl-C signal is received.
+///
+/// See [on_shutdown_with_timeout()](fn.on_shutdown_with_timeout.html), this waits for the hook
+/// to return however long it takes.
+///
+/// # Errors
+/// Will return an error if a system error occurred while setting the handler.
+///
+pub fn on_shutdown<S, F>(name: S, priority: i32, f: F) -> Result<(), Error>
+where
+    S: Into<String>,
+    F: FnOnce() + 'static + Send,
+{
+    register(name.into(), priority, None, Vec::new(), Box::new(f))
+}
+
+/// Register a hook that runs once when a Ctrl-C signal is received, and is given up on after
+/// `timeout`.
+///
+/// The hooks run one after another on the dedicated signal handling thread, after the handler
+/// closure and all [subscribers](fn.subscribe.html). Hooks with a higher priority run first,
+/// hooks of equal priority in reverse registration order, so that something registered later,
+/// and possibly depending on what was registered before, is cleaned up first. Use
+/// [on_shutdown_with_dependencies()](fn.on_shutdown_with_dependencies.html) to run a hook
+/// before specific other hooks instead. Each hook runs on a thread of its own, so that a hook
+/// that hangs or panics does not keep the others from running. The outcome is available from
+/// [shutdown_summary()](fn.shutdown_summary.html) afterwards.
+///
+/// Any number of hooks can be registered, e.g. by independent libraries. If no handler is
+/// registered yet, one is registered for the default signals, without a closure of its own.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+///
+/// ctrlc::on_shutdown_with_timeout("flush logs", 0, Duration::from_secs(1), || {
+///     println!("Flushing logs");
+/// })
+/// .expect("Error setting Ctrl-C handler");
+/// ctrlc::set_policy(ctrlc::Policy::ExitBySignal);
+/// ```
+///
+/// # Errors
+/// Will return an error if a system error occurred while setting the handler.
+///
+pub fn on_shutdown_with_timeout<S, F>(
+    name: S,
+    priority: i32,
+    timeout: Duration,
+    f: F,
+) -> Result<(), Error>
+where
+    S: Into<String>,
+    F: FnOnce() + 'static + Send,
+{
+    register(
+        name.into(),
+        priority,
+        Some(timeout),
+        Vec::new(),
+        Box::new(f),
+    )
+}
+
+/// Register a hook that runs once when a Ctrl-C signal is received, before the hooks named in
+/// `dependencies`.
+///
+/// The hook runs before the hooks it depends on whatever their priority, e.g. to flush a cache
+/// into a connection pool that another hook closes. Names of hooks that are not registered are
+/// ignored, and hooks that depend on each other in a cycle run in priority order. With a
+/// `timeout`, the hook is given up on after it, see
+/// [on_shutdown_with_timeout()](fn.on_shutdown_with_timeout.html).
+///
+/// # Example
+/// ```no_run
+/// ctrlc::on_shutdown("close pool", 0, || println!("Closing the connection pool"))
+///     .expect("Error setting Ctrl-C handler");
+/// ctrlc::on_shutdown_with_dependencies("flush cache", -1, None, &["close pool"], || {
+///     println!("Flushing the cache")
+/// })
+/// .expect("Error setting Ctrl-C handler");
+/// ```
+///
+/// # Errors
+/// Will return an error if a system error occurred while setting the handler.
+///
+pub fn on_shutdown_with_dependencies<S, F>(
+    name: S,
+    priority: i32,
+    timeout: Option<Duration>,
+    dependencies: &[&str],
+    f: F,
+) -> Result<(), Error>
+where
+    S: Into<String>,
+    F: FnOnce() + 'static + Send,
+{
+    let dependencies = dependencies.iter().map(|&d| d.to_owned()).collect();
+    register(name.into(), priority, timeout, dependencies, Box::new(f))
+}
+
+/// Run the registered shutdown hooks now, e.g. for a shutdown that was not caused by a signal.
+///
+/// Hooks only run once, they are removed from the registry before they run.
+pub fn run_shutdown_hooks() -> ShutdownSummary {
+    run_hooks().unwrap_or_default()
+}
+
+/// The outcome of the shutdown hooks that ran on the latest signal, `None` if no signal was
+/// received since the first hook was registered. Signals received once the hooks ran leave
+/// it as it is.
+pub fn shutdown_summary() -> Option<ShutdownSummary> {
+    SUMMARY.lock().unwrap().clone()
+}
+
+/// Run the registered hooks, `None` if there were none.
+fn run_hooks() -> Option<ShutdownSummary> {
+    let hooks = {
+        let mut subscription = SUBSCRIPTION.lock().unwrap();
+        if let Some(id) = subscription.take() {
+            subscriber::unsubscribe(id);
+        }
+        mem::take(&mut *HOOKS.lock().unwrap())
+    };
+    if hooks.is_empty() {
+        return None;
+    }
+    let mut summary = ShutdownSummary::default();
+
+    for hook in order(hooks) {
+        let (tx, rx) = mpsc::channel();
+        let f = hook.f;
+        let spawned = thread::Builder::new()
+            .name(hook.name.clone())
+            .spawn(move || {
+                let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(f)).is_ok());
+            });
+
+        let outcome = match (spawned, hook.timeout) {
+            (Err(_), _) => Ok(false),
+            (Ok(_), Some(timeout)) => rx.recv_timeout(timeout).map_err(|_| ()),
+            (Ok(_), None) => rx.recv().map_err(|_| ()),
+        };
+
+        match outcome {
+            Ok(true) => summary.finished.push(hook.name),
+            Ok(false) => summary.failed.push(hook.name),
+            Err(()) => summary.timed_out.push(hook.name),
+        }
+    }
+
+    Some(summary)
+}
+
+/// Reorder the hooks so that each runs before the hooks it depends on, keeping their order
+/// otherwise.
+fn order(mut hooks: Vec<Hook>) -> Vec<Hook> {
+    let mut ordered = Vec::with_capacity(hooks.len());
+    while !hooks.is_empty() {
+        // The first hook no other remaining hook depends on, or just the first in a cycle.
+        let index = (0..hooks.len())
+            .find(|&i| {
+                !hooks
+                    .iter()
+                    .enumerate()
+                    .any(|(j, h)| j != i && h.dependencies.contains(&hooks[i].name))
+            })
+            .unwrap_or(0);
+        ordered.push(hooks.remove(index));
+    }
+    ordered
+}
+
+fn register(
+    name: String,
+    priority: i32,
+    timeout: Option<Duration>,
+    dependencies: Vec<String>,
+    f: Box<dyn FnOnce() + Send>,
+) -> Result<(), Error> {
+    // The handler may have been removed since the hook subscriber was added.
+    handler::ensure_installed()?;
+
+    let mut subscription = SUBSCRIPTION.lock().unwrap();
+    {
+        let mut hooks = HOOKS.lock().unwrap();
+        let index = hooks
+            .iter()
+            .position(|h| h.priority <= priority)
+            .unwrap_or(hooks.len());
+        hooks.insert(
+            index,
+            Hook {
+                name,
+                priority,
+                timeout,
+                dependencies,
+                f,
+            },
+        );
+    }
+
+    if subscription.is_none() {
+        // The hooks run after everything else.
+        let result = subscriber::subscribe(
+            i32::MIN,
+            Box::new(|_| {
+                if let Some(summary) = run_hooks() {
+                    *SUMMARY.lock().unwrap() = Some(summary);
+                }
+            }),
+        );
+        match result {
+            Ok(id) => *subscription = Some(id),
+            Err(err) => {
+                HOOKS.lock().unwrap().clear();
+                return Err(err);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -11,13 +11,49 @@ use platform;
 
 /// A cross-platform way to represent Ctrl-C or program termination signal. Other
 /// signals/events are supported via `Other`-variant.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum SignalType {
     /// Ctrl-C
     Ctrlc,
     /// Program termination
     /// Maps to `SIGTERM` on *nix, `CTRL_CLOSE_EVENT` on Windows.
     Termination,
-    /// Other signal/event using platform-specific data
+    /// The controlling terminal was closed
+    /// Maps to `SIGHUP` on *nix, not available on Windows.
+    Hangup,
+    /// Quit from the keyboard, usually with `Ctrl+\`
+    /// Maps to `SIGQUIT` on *nix, not available on Windows.
+    Quit,
+    /// Other signal/event using platform-specific data, e.g. `SIGPIPE`
+    /// Signals that have a variant of their own are delivered as that variant.
     Other(platform::Signal),
 }
+
+/// Details about a received signal, as far as the platform provides them.
+///
+/// On Unix, these come from the `siginfo_t` the signal was delivered with. On Windows, only
+/// `signal` is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SignalInfo {
+    /// The signal that was received.
+    pub signal: SignalType,
+    /// Process id of the sender (`si_pid`), if the signal was sent by another process, for
+    /// example with `kill(2)`. `None` for signals generated by the kernel or the terminal.
+    pub pid: Option<u32>,
+    /// Real user id of the sender (`si_uid`), known whenever `pid` is.
+    pub uid: Option<u32>,
+    /// Why the signal was sent (`si_code`), e.g. `SI_USER` for `kill(2)`.
+    pub code: Option<i32>,
+}
+
+impl From<SignalType> for SignalInfo {
+    fn from(signal: SignalType) -> SignalInfo {
+        SignalInfo {
+            signal,
+            pid: None,
+            uid: None,
+            code: None,
+        }
+    }
+}
@@ -0,0 +1,46 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use platform;
+
+/// Counters of the signals handled since the program started, returned by
+/// [stats()](fn.stats.html).
+///
+/// Signals that were received but neither delivered nor dropped yet are still waiting for the
+/// signal handling thread or for [try_recv()](fn.try_recv.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+    /// Signals caught by the OS signal handler.
+    pub received: u64,
+    /// Signals passed on to the handler closure, a subscriber or a polling function.
+    pub delivered: u64,
+    /// Signals that arrived while the queue between the OS signal handler and the signal
+    /// handling thread was full, or had been full and was not read past that point yet. They
+    /// are still delivered after the signals that were queued before them, but grouped by
+    /// signal rather than in the order they arrived in, and their
+    /// [SignalInfo](struct.SignalInfo.html) only holds the signal.
+    pub overflowed: u64,
+    /// Signals that were lost, for example because the handler was removed before they were
+    /// delivered, or dropped while [ignored](struct.Ignore.html).
+    pub dropped: u64,
+}
+
+/// Count the signals handled since the program started.
+///
+/// # Example
+/// ```no_run
+/// let stats = ctrlc::stats();
+/// if stats.dropped > 0 {
+///     eprintln!("{} signals were lost", stats.dropped);
+/// }
+/// ```
+///
+pub fn stats() -> Stats {
+    platform::stats()
+}
@@ -0,0 +1,116 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Parts shared by the async runtime integrations.
+
+extern crate futures_core;
+
+pub use self::futures_core::Stream;
+
+use error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use SignalType;
+
+/// A stream of received signals, implemented once per runtime.
+pub trait PollRecv: Sized + Unpin {
+    /// Create the stream. Called from within the runtime.
+    fn new() -> Result<Self, Error>;
+
+    /// Poll for the next received signal.
+    fn poll_recv(&mut self, cx: &mut Context) -> Poll<Result<SignalType, Error>>;
+}
+
+/// Future that resolves with the next received signal.
+///
+/// The stream is created on the first poll, so that it is created from within the runtime.
+#[derive(Debug)]
+pub struct CtrlC<S> {
+    stream: Option<S>,
+}
+
+impl<S> Default for CtrlC<S> {
+    fn default() -> CtrlC<S> {
+        CtrlC { stream: None }
+    }
+}
+
+impl<S: PollRecv> Future for CtrlC<S> {
+    type Output = Result<SignalType, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if self.stream.is_none() {
+            self.stream = Some(S::new()?);
+        }
+        self.stream.as_mut().unwrap().poll_recv(cx)
+    }
+}
+
+/// There is nothing to register with a reactor on Windows, so a thread waits for the signals
+/// and wakes up the streams.
+#[cfg(windows)]
+pub mod forwarded {
+    use error::{self, Error};
+    use platform;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+    use std::task::{Context, Poll, Waker};
+    use std::thread;
+    use SignalType;
+
+    struct Queue {
+        signals: VecDeque<SignalType>,
+        wakers: Vec<Waker>,
+        started: bool,
+    }
+
+    static QUEUE: Mutex<Queue> = Mutex::new(Queue {
+        signals: VecDeque::new(),
+        wakers: Vec::new(),
+        started: false,
+    });
+
+    fn forward() {
+        loop {
+            let info = match unsafe { platform::block_ctrl_c() } {
+                Ok(info) => info,
+                Err(err) => return error::report(err),
+            };
+            if let Some(info) = info {
+                let mut queue = QUEUE.lock().unwrap();
+                queue.signals.push_back(info.signal);
+                for waker in queue.wakers.drain(..) {
+                    waker.wake();
+                }
+            }
+        }
+    }
+
+    pub fn poll_recv(cx: &mut Context) -> Poll<Result<SignalType, Error>> {
+        let mut queue = QUEUE.lock().unwrap();
+        if !queue.started {
+            thread::Builder::new()
+                .name("ctrl-c".into())
+                .spawn(forward)
+                .map_err(Error::ThreadSpawn)?;
+            queue.started = true;
+        }
+
+        match queue.signals.pop_front() {
+            Some(signal) => Poll::Ready(Ok(signal)),
+            None => {
+                if !queue.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    queue.wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use error::Error;
+use handler::{self, BoxedHandler, InfoHandler, SharedHandler};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use SignalInfo;
+use SignalType;
+
+/// Identifies a subscriber registered with [subscribe()](fn.subscribe.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(usize);
+
+struct Subscriber {
+    id: SubscriptionId,
+    priority: i32,
+    handler: SharedHandler,
+}
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+// Kept sorted by descending priority, subscribers with equal priority in registration order.
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+
+/// Add a subscriber and make sure there is a handler to call it.
+pub fn subscribe(priority: i32, mut handler: BoxedHandler) -> Result<SubscriptionId, Error> {
+    subscribe_with_info(
+        priority,
+        Box::new(move |info: SignalInfo| handler(info.signal)),
+    )
+}
+
+/// Add a subscriber that is told the details of the signal.
+pub fn subscribe_with_info(priority: i32, handler: InfoHandler) -> Result<SubscriptionId, Error> {
+    let id = SubscriptionId(NEXT_ID.fetch_add(1, Ordering::SeqCst));
+
+    {
+        let mut subscribers = SUBSCRIBERS.lock().unwrap();
+        let index = subscribers
+            .iter()
+            .position(|s| s.priority < priority)
+            .unwrap_or(subscribers.len());
+        subscribers.insert(
+            index,
+            Subscriber {
+                id,
+                priority,
+                handler: Arc::new(Mutex::new(handler)),
+            },
+        );
+    }
+
+    if let Err(err) = handler::ensure_installed() {
+        unsubscribe(id);
+        return Err(err);
+    }
+
+    Ok(id)
+}
+
+/// Subscribe a closure that is called for every signal until it returns `false`, e.g.
+/// because the receiving end of a channel was dropped. The subscriber then removes itself.
+pub fn subscribe_while<F>(f: F) -> Result<(), Error>
+where
+    F: Fn(SignalType) -> bool + 'static + Send,
+{
+    let id: Arc<Mutex<Option<SubscriptionId>>> = Arc::new(Mutex::new(None));
+    let own_id = id.clone();
+    let subscribed = subscribe(
+        0,
+        Box::new(move |signal| {
+            if !f(signal) {
+                if let Some(id) = own_id.lock().unwrap().take() {
+                    unsubscribe(id);
+                }
+            }
+        }),
+    )?;
+    *id.lock().unwrap() = Some(subscribed);
+    Ok(())
+}
+
+/// Remove a subscriber. Returns `false` if it was not registered.
+pub fn unsubscribe(id: SubscriptionId) -> bool {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+    match subscribers.iter().position(|s| s.id == id) {
+        Some(index) => {
+            subscribers.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Whether no subscriber is registered.
+pub fn is_empty() -> bool {
+    SUBSCRIBERS.lock().unwrap().is_empty()
+}
+
+/// The subscribers in dispatch order, split into the ones to call before the handler closure
+/// (positive priority) and the ones to call after it.
+pub fn snapshot() -> (Vec<SharedHandler>, Vec<SharedHandler>) {
+    let subscribers = SUBSCRIBERS.lock().unwrap();
+    let split = subscribers
+        .iter()
+        .position(|s| s.priority <= 0)
+        .unwrap_or(subscribers.len());
+    let handlers = subscribers.iter().map(|s| s.handler.clone());
+    (
+        handlers.clone().take(split).collect(),
+        handlers.skip(split).collect(),
+    )
+}
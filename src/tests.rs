@@ -11,7 +11,7 @@ extern crate ctrlc;
 
 #[cfg(unix)]
 mod platform {
-    extern crate nix;
+    pub extern crate nix;
 
     use std::io;
 
@@ -23,8 +23,11 @@ mod platform {
         Ok(())
     }
 
+    /// Signal the whole process rather than the calling thread, which the signalfd backend
+    /// would not see while reading from another thread.
     pub unsafe fn raise_ctrl_c() {
-        self::nix::sys::signal::raise(self::nix::sys::signal::SIGINT).unwrap();
+        self::nix::sys::signal::kill(self::nix::unistd::getpid(), self::nix::sys::signal::SIGINT)
+            .unwrap();
     }
 
     pub unsafe fn print(fmt: ::std::fmt::Arguments) {
@@ -226,6 +229,882 @@ mod platform {
     }
 }
 
+fn test_handler_drop() {
+    for _ in 0..2 {
+        let (tx, rx) = ::std::sync::mpsc::channel();
+        let handler = ctrlc::Handler::install_with_signal(move |signal| {
+            tx.send(signal).unwrap();
+        })
+        .unwrap();
+
+        match ctrlc::set_handler(|| {}) {
+            Err(ctrlc::Error::MultipleHandlers) => {}
+            ret => panic!("{:?}", ret),
+        }
+
+        unsafe {
+            platform::raise_ctrl_c();
+        }
+
+        let signal = rx
+            .recv_timeout(::std::time::Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(signal, ctrlc::SignalType::Ctrlc);
+
+        drop(handler);
+
+        // Nothing stays blocked on the thread that installed the handler.
+        #[cfg(unix)]
+        {
+            use platform::nix::sys::signal::{SigSet, Signal};
+            let mask = SigSet::thread_get_mask().unwrap();
+            assert!(!mask.contains(Signal::SIGINT));
+        }
+    }
+}
+
+#[cfg(unix)]
+fn test_no_such_signal() {
+    let kill = ctrlc::SignalType::Other(ctrlc::Signal::SIGKILL);
+    match ctrlc::set_handler_for(&[ctrlc::SignalType::Ctrlc, kill], |_| {}) {
+        Err(ctrlc::Error::NoSuchSignal(signal)) => assert_eq!(signal, kill),
+        ret => panic!("{:?}", ret),
+    }
+
+    // Nothing was left installed.
+    let handler = ctrlc::Handler::install(|| {}).unwrap();
+    drop(handler);
+}
+
+#[cfg(windows)]
+fn test_no_such_signal() {}
+
+fn test_replace_handler() {
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let first = tx.clone();
+    let handler = ctrlc::Handler::install(move || {
+        first.send("first").unwrap();
+    })
+    .unwrap();
+
+    let previous = ::std::sync::Arc::new(::std::sync::Mutex::new(None));
+    let chained = previous.clone();
+    *previous.lock().unwrap() = ctrlc::replace_handler(move |signal| {
+        tx.send("second").unwrap();
+        let mut chained = chained.lock().unwrap();
+        let chained: &mut ctrlc::BoxedHandler = chained.as_mut().unwrap();
+        chained(signal);
+    })
+    .unwrap();
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    let timeout = ::std::time::Duration::from_secs(10);
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), "second");
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), "first");
+
+    drop(handler);
+}
+
+fn test_subscribe() {
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let handler_tx = tx.clone();
+    let handler = ctrlc::Handler::install(move || {
+        handler_tx.send("handler").unwrap();
+    })
+    .unwrap();
+
+    let low_tx = tx.clone();
+    let low = ctrlc::subscribe(move |_| {
+        low_tx.send("low").unwrap();
+    })
+    .unwrap();
+    let high = ctrlc::subscribe_with_priority(1, move |_| {
+        tx.send("high").unwrap();
+    })
+    .unwrap();
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    let timeout = ::std::time::Duration::from_secs(10);
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), "high");
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), "handler");
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), "low");
+
+    assert!(ctrlc::unsubscribe(low));
+    assert!(ctrlc::unsubscribe(high));
+    assert!(!ctrlc::unsubscribe(high));
+
+    drop(handler);
+}
+
+fn test_channel() {
+    let handler = ctrlc::Handler::install(|| {}).unwrap();
+    let signals = ctrlc::channel().unwrap();
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    let signal = signals
+        .recv_timeout(::std::time::Duration::from_secs(10))
+        .unwrap();
+    assert_eq!(signal, ctrlc::SignalType::Ctrlc);
+
+    // The subscriber of the channel is removed on the next signal, before the token that was
+    // subscribed after it is cancelled. Otherwise it would keep the thread running.
+    drop(signals);
+    let token = ctrlc::ShutdownToken::new().unwrap();
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+    assert!(token.wait_timeout(::std::time::Duration::from_secs(10)));
+
+    drop(handler);
+}
+
+fn test_shutdown_token() {
+    let handler = ctrlc::Handler::install(|| {}).unwrap();
+    let token = ctrlc::ShutdownToken::new().unwrap();
+    let clone = token.clone();
+    let child = token.child_token();
+    let cancelled_child = token.child_token();
+
+    cancelled_child.cancel();
+    assert!(cancelled_child.is_cancelled());
+    assert!(!token.is_cancelled());
+    assert!(!token.wait_timeout(::std::time::Duration::from_millis(10)));
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    assert!(clone.wait_timeout(::std::time::Duration::from_secs(10)));
+    assert!(token.is_cancelled());
+    assert!(child.is_cancelled());
+    assert!(token.child_token().is_cancelled());
+    child.wait();
+
+    drop(handler);
+}
+
+fn test_shutdown_hooks() {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    let handler = ctrlc::Handler::install(|| {}).unwrap();
+    let ran = Arc::new(Mutex::new(Vec::new()));
+    for &(name, priority) in &[("first", 0), ("second", 0), ("urgent", 1)] {
+        let ran = ran.clone();
+        ctrlc::on_shutdown(name, priority, move || ran.lock().unwrap().push(name)).unwrap();
+    }
+    {
+        let ran = ran.clone();
+        ctrlc::on_shutdown_with_dependencies("flush", -2, None, &["second"], move || {
+            ran.lock().unwrap().push("flush")
+        })
+        .unwrap();
+    }
+    // The hook returns at the end of the test, a thread left running would get the signals
+    // of later tests with the signalfd backend.
+    let (done_tx, done_rx) = ::std::sync::mpsc::channel::<()>();
+    ctrlc::on_shutdown_with_timeout("slow", -1, Duration::from_millis(10), move || {
+        let _ = done_rx.recv();
+    })
+    .unwrap();
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    let mut summary = None;
+    for _ in 0..1000 {
+        summary = ctrlc::shutdown_summary();
+        if summary.is_some() {
+            break;
+        }
+        ::std::thread::sleep(Duration::from_millis(10));
+    }
+    let summary = summary.unwrap();
+    assert_eq!(*ran.lock().unwrap(), ["urgent", "first", "flush", "second"]);
+    assert_eq!(summary.finished, ["urgent", "first", "flush", "second"]);
+    assert!(summary.failed.is_empty());
+    assert_eq!(summary.timed_out, ["slow"]);
+    drop(done_tx);
+
+    // A signal without hooks left to run keeps the summary. Subscribers of equal priority run
+    // in registration order, so this one runs after the hooks.
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let id = ctrlc::subscribe_with_priority(i32::MIN, move |_| {
+        let _ = tx.send(());
+    })
+    .unwrap();
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+    rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(ctrlc::shutdown_summary(), Some(summary));
+    ctrlc::unsubscribe(id);
+
+    // Keep the panic from reaching the hook of the test binary, which cleans up.
+    let std_hook = ::std::panic::take_hook();
+    ::std::panic::set_hook(Box::new(|_| {}));
+    ctrlc::on_shutdown("panics", 0, || panic!("hook panic")).unwrap();
+    let summary = ctrlc::run_shutdown_hooks();
+    ::std::panic::set_hook(std_hook);
+
+    assert_eq!(summary.failed, ["panics"]);
+    assert_eq!(
+        ctrlc::run_shutdown_hooks(),
+        ctrlc::ShutdownSummary::default()
+    );
+
+    drop(handler);
+}
+
+fn test_defer() {
+    use std::time::Duration;
+
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let handler =
+        ctrlc::Handler::install_with_signal(move |signal| tx.send(signal).unwrap()).unwrap();
+
+    let guard = ctrlc::defer();
+    let nested = ctrlc::defer();
+    let delivered = ctrlc::stats().delivered;
+    for i in 1..3 {
+        unsafe {
+            platform::raise_ctrl_c();
+        }
+        // Wait for the signal to be read, so that the signalfd backend does not merge them.
+        for _ in 0..1000 {
+            if ctrlc::stats().delivered == delivered + i {
+                break;
+            }
+            ::std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    drop(nested);
+    assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+    drop(guard);
+    for _ in 0..2 {
+        let signal = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(signal, ctrlc::SignalType::Ctrlc);
+    }
+
+    drop(handler);
+}
+
+fn test_ignore() {
+    use std::time::Duration;
+
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let handler =
+        ctrlc::Handler::install_with_signal(move |signal| tx.send(signal).unwrap()).unwrap();
+
+    // Dropped as they arrive, not when the signal handling thread gets to read them.
+    ctrlc::ignore_while(|| {
+        for _ in 0..100 {
+            unsafe {
+                platform::raise_ctrl_c();
+            }
+        }
+    })
+    .unwrap();
+    assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+    #[cfg(unix)]
+    {
+        let kill = ctrlc::SignalType::Other(ctrlc::Signal::SIGKILL);
+        match ctrlc::Ignore::new(&[ctrlc::SignalType::Ctrlc, kill]) {
+            Err(ctrlc::Error::NoSuchSignal(signal)) => assert_eq!(signal, kill),
+            ret => panic!("{:?}", ret),
+        }
+    }
+
+    let guard = ctrlc::Ignore::new(&[ctrlc::SignalType::Ctrlc]).unwrap();
+    drop(guard);
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+    let signal = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(signal, ctrlc::SignalType::Ctrlc);
+
+    drop(handler);
+}
+
+#[cfg(unix)]
+fn test_forward() {
+    use std::os::unix::process::{CommandExt, ExitStatusExt};
+    use std::process::{Command, Stdio};
+    use std::sync::{Arc, Mutex};
+
+    // The signalfd backend leaves SIGINT blocked, which children would inherit.
+    let spawn = |command: &mut Command| unsafe {
+        command
+            .pre_exec(|| {
+                platform::nix::sys::signal::SigSet::empty()
+                    .thread_set_mask()
+                    .map_err(::std::io::Error::other)
+            })
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap()
+    };
+    let sleeping = spawn(Command::new("sleep").arg("60"));
+    let stubborn = spawn(Command::new("sh").args(["-c", "trap '' INT; exec sleep 60"]));
+    let children = Arc::new(Mutex::new((sleeping, stubborn)));
+
+    // Give sh time to ignore SIGINT.
+    ::std::thread::sleep(::std::time::Duration::from_millis(200));
+
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let own_children = children.clone();
+    let handler = ctrlc::Handler::install(move || {
+        let mut children = own_children.lock().unwrap();
+        let sleeping = children.0.try_wait().unwrap();
+        let stubborn = children.1.try_wait().unwrap();
+        tx.send((sleeping, stubborn)).unwrap();
+    })
+    .unwrap();
+
+    let targets = {
+        let children = children.lock().unwrap();
+        ctrlc::forward_to_child(&children.0).unwrap();
+        ctrlc::forward_to_child(&children.1).unwrap();
+        [
+            ctrlc::ForwardTarget::Process(children.0.id()),
+            ctrlc::ForwardTarget::Process(children.1.id()),
+        ]
+    };
+    ctrlc::set_kill_after(Some(::std::time::Duration::from_millis(100)));
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    let (sleeping, stubborn) = rx
+        .recv_timeout(::std::time::Duration::from_secs(10))
+        .unwrap();
+    assert_eq!(sleeping.unwrap().signal(), Some(2));
+    assert_eq!(stubborn.unwrap().signal(), Some(9));
+
+    for &target in &targets {
+        assert!(ctrlc::stop_forwarding(target));
+    }
+    ctrlc::set_kill_after(None);
+    drop(handler);
+}
+
+#[cfg(windows)]
+fn test_forward() {}
+
+#[cfg(unix)]
+fn test_install_mode() {
+    use ctrlc::InstallMode;
+    use platform::nix::sys::signal::{self, SaFlags, SigAction, SigHandler, SigSet};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static FOREIGN_CALLED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn foreign_handler(_: ::std::os::raw::c_int) {
+        FOREIGN_CALLED.store(true, Ordering::SeqCst);
+    }
+
+    let foreign = SigAction::new(
+        SigHandler::Handler(foreign_handler),
+        SaFlags::empty(),
+        SigSet::empty(),
+    );
+    let default = unsafe { signal::sigaction(signal::SIGINT, &foreign).unwrap() };
+
+    ctrlc::set_install_mode(InstallMode::Strict);
+    match ctrlc::Handler::install(|| {}) {
+        Err(ctrlc::Error::HandlerAlreadyInstalled(ctrlc::SignalType::Ctrlc)) => {}
+        ret => panic!("{:?}", ret),
+    }
+
+    ctrlc::set_install_mode(InstallMode::Chain);
+
+    // The signalfd backend has no signal handler to chain from.
+    #[cfg(all(feature = "signalfd", any(target_os = "linux", target_os = "android")))]
+    match ctrlc::Handler::install(|| {}) {
+        Err(ctrlc::Error::System(_)) => {}
+        ret => panic!("{:?}", ret),
+    }
+
+    #[cfg(not(all(feature = "signalfd", any(target_os = "linux", target_os = "android"))))]
+    {
+        let (tx, rx) = ::std::sync::mpsc::channel();
+        let handler = ctrlc::Handler::install(move || {
+            tx.send(true).unwrap();
+        })
+        .unwrap();
+
+        unsafe {
+            platform::raise_ctrl_c();
+        }
+
+        rx.recv_timeout(::std::time::Duration::from_secs(10))
+            .unwrap();
+        assert!(FOREIGN_CALLED.load(Ordering::SeqCst));
+
+        drop(handler);
+    }
+
+    ctrlc::set_install_mode(InstallMode::Overwrite);
+    unsafe {
+        signal::sigaction(signal::SIGINT, &default).unwrap();
+    }
+}
+
+#[cfg(windows)]
+fn test_install_mode() {}
+
+#[cfg(unix)]
+fn test_hangup() {
+    use ctrlc::{InstallMode, SignalType};
+    use platform::nix::sys::signal::{self, SaFlags, SigAction, SigHandler, SigSet};
+    use platform::nix::unistd;
+
+    extern "C" fn foreign_handler(_: ::std::os::raw::c_int) {}
+
+    let foreign = SigAction::new(
+        SigHandler::Handler(foreign_handler),
+        SaFlags::empty(),
+        SigSet::empty(),
+    );
+    let default = unsafe { signal::sigaction(signal::SIGHUP, &foreign).unwrap() };
+
+    // SIGINT is registered before SIGHUP fails, and must be rolled back.
+    ctrlc::set_install_mode(InstallMode::Strict);
+    match ctrlc::Handler::install_for(&[SignalType::Ctrlc, SignalType::Hangup], |_| {}) {
+        Err(ctrlc::Error::HandlerAlreadyInstalled(SignalType::Hangup)) => {}
+        ret => panic!("{:?}", ret),
+    }
+    ctrlc::set_install_mode(InstallMode::Overwrite);
+    unsafe {
+        let current = signal::sigaction(signal::SIGINT, &default).unwrap();
+        assert_eq!(current.handler(), SigHandler::SigDfl);
+        signal::sigaction(signal::SIGHUP, &default).unwrap();
+    }
+
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let handler = ctrlc::Handler::install_for(&[SignalType::Hangup], move |signal| {
+        tx.send(signal).unwrap();
+    })
+    .unwrap();
+
+    signal::kill(unistd::getpid(), signal::SIGHUP).unwrap();
+
+    let signal = rx
+        .recv_timeout(::std::time::Duration::from_secs(10))
+        .unwrap();
+    assert_eq!(signal, SignalType::Hangup);
+
+    drop(handler);
+}
+
+#[cfg(windows)]
+fn test_hangup() {
+    match ctrlc::Handler::install_for(&[ctrlc::SignalType::Hangup], |_| {}) {
+        Err(ctrlc::Error::NoSuchSignal(ctrlc::SignalType::Hangup)) => {}
+        ret => panic!("{:?}", ret),
+    }
+}
+
+fn test_signal_info() {
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let handler = ctrlc::Handler::install_with_info(move |info| {
+        tx.send(info).unwrap();
+    })
+    .unwrap();
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    let info = rx
+        .recv_timeout(::std::time::Duration::from_secs(10))
+        .unwrap();
+    assert_eq!(info.signal, ctrlc::SignalType::Ctrlc);
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    assert_eq!(info.pid, Some(::std::process::id()));
+
+    drop(handler);
+}
+
+fn test_stats() {
+    let before = ctrlc::stats();
+    let handler = ctrlc::Handler::install(|| {}).unwrap();
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let id = ctrlc::subscribe(move |_| {
+        let _ = tx.send(());
+    })
+    .unwrap();
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    rx.recv_timeout(::std::time::Duration::from_secs(10))
+        .unwrap();
+    ctrlc::unsubscribe(id);
+    drop(handler);
+
+    let after = ctrlc::stats();
+    assert_eq!(after.received, before.received + 1);
+    assert_eq!(after.delivered, before.delivered + 1);
+    assert_eq!(after.dropped, before.dropped);
+}
+
+// Scenarios that end the process, run in a child process by test_exit().
+#[cfg(unix)]
+mod child {
+    use platform;
+    use std::process;
+    use std::thread;
+    use std::time::Duration;
+
+    pub const SCENARIO: &str = "CTRLC_TEST_SCENARIO";
+
+    pub fn run(scenario: &str) -> ! {
+        match scenario {
+            "force_exit" => {
+                ctrlc::set_policy(ctrlc::Policy::ForceExitAfter {
+                    count: 2,
+                    code: 42,
+                    window: None,
+                });
+                ctrlc::set_handler(|| {
+                    thread::sleep(Duration::from_secs(3));
+                    println!("handler end");
+                })
+                .unwrap();
+                unsafe {
+                    platform::raise_ctrl_c();
+                }
+                thread::sleep(Duration::from_millis(200));
+                unsafe {
+                    platform::raise_ctrl_c();
+                }
+            }
+            "timeout" => {
+                ctrlc::set_handler_with_timeout(
+                    |_| thread::sleep(Duration::from_secs(10)),
+                    Duration::from_millis(100),
+                    ctrlc::TimeoutAction::Exit(43),
+                )
+                .unwrap();
+                unsafe {
+                    platform::raise_ctrl_c();
+                }
+            }
+            "exit_by_signal" => {
+                ctrlc::set_handler_with_signal(|signal| ctrlc::exit_by_signal(signal)).unwrap();
+                unsafe {
+                    platform::raise_ctrl_c();
+                }
+            }
+            "hooks_after_drop" => {
+                let handler = ctrlc::Handler::install(|| {}).unwrap();
+                ctrlc::on_shutdown("first", 0, || {}).unwrap();
+                drop(handler);
+                ctrlc::on_shutdown("exit", 0, || process::exit(44)).unwrap();
+                unsafe {
+                    platform::raise_ctrl_c();
+                }
+            }
+            "forward_after_drop" => {
+                use std::os::unix::process::CommandExt;
+                use std::process::{Command, Stdio};
+
+                let spawn = || unsafe {
+                    Command::new("sleep")
+                        .arg("10")
+                        .pre_exec(|| {
+                            platform::nix::sys::signal::SigSet::empty()
+                                .thread_set_mask()
+                                .map_err(::std::io::Error::other)
+                        })
+                        .stdout(Stdio::null())
+                        .stderr(Stdio::null())
+                        .spawn()
+                        .unwrap()
+                };
+
+                let handler = ctrlc::Handler::install(|| {}).unwrap();
+                let mut first = spawn();
+                ctrlc::forward_to_child(&first).unwrap();
+                drop(handler);
+                let mut second = spawn();
+                ctrlc::forward_to_child(&second).unwrap();
+                unsafe {
+                    platform::raise_ctrl_c();
+                }
+                first.wait().unwrap();
+                second.wait().unwrap();
+                process::exit(45);
+            }
+            "token_after_drop" => {
+                let handler = ctrlc::Handler::install(|| {}).unwrap();
+                let token = ctrlc::ShutdownToken::new().unwrap();
+                drop(handler);
+                unsafe {
+                    platform::raise_ctrl_c();
+                }
+                if token.wait_timeout(Duration::from_secs(10)) {
+                    process::exit(46);
+                }
+            }
+            "forward_terminal" => {
+                use platform::nix::libc;
+                use platform::nix::pty::openpty;
+                use platform::nix::unistd::{self, setsid};
+                use std::io::{BufRead, BufReader, Read};
+                use std::os::unix::process::CommandExt;
+                use std::process::{Command, Stdio};
+
+                // Ctrl-C on the controlling terminal signals the whole process group.
+                setsid().unwrap();
+                let pty = openpty(None, None).unwrap();
+                assert_eq!(
+                    unsafe { libc::ioctl(pty.slave, libc::TIOCSCTTY as _, 0) },
+                    0
+                );
+
+                ctrlc::set_handler(|| {}).unwrap();
+                let mut reporter = unsafe {
+                    Command::new(::std::env::current_exe().unwrap())
+                        .env(SCENARIO, "report_signals")
+                        .pre_exec(|| {
+                            platform::nix::sys::signal::SigSet::empty()
+                                .thread_set_mask()
+                                .map_err(::std::io::Error::other)
+                        })
+                        .stdout(Stdio::piped())
+                        .spawn()
+                        .unwrap()
+                };
+                let mut output = BufReader::new(reporter.stdout.take().unwrap());
+                let mut ready = String::new();
+                output.read_line(&mut ready).unwrap();
+                ctrlc::forward_to_child(&reporter).unwrap();
+
+                unistd::write(pty.master, b"\x03").unwrap();
+                reporter.wait().unwrap();
+                let mut received = String::new();
+                output.read_to_string(&mut received).unwrap();
+                // Only the terminal signalled the reporter.
+                if received == "None\n" {
+                    process::exit(47);
+                }
+                eprintln!("{:?}", received);
+                process::exit(1);
+            }
+            "report_signals" => {
+                ctrlc::set_handler_with_info(|info| println!("{:?}", info.pid)).unwrap();
+                println!("ready");
+                thread::sleep(Duration::from_secs(1));
+                process::exit(0);
+            }
+            _ => panic!("unknown scenario {}", scenario),
+        }
+
+        thread::sleep(Duration::from_secs(10));
+        process::exit(0);
+    }
+}
+
+#[cfg(unix)]
+fn test_exit() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+    use std::time::{Duration, Instant};
+
+    let run = |scenario: &str| {
+        let start = Instant::now();
+        let output = Command::new(::std::env::current_exe().unwrap())
+            .env(child::SCENARIO, scenario)
+            .output()
+            .unwrap();
+        (output, start.elapsed())
+    };
+
+    // The second Ctrl-C exits while the handler is still busy with the first one.
+    let (output, elapsed) = run("force_exit");
+    assert_eq!(output.status.code(), Some(42));
+    assert!(output.stdout.is_empty());
+    assert!(elapsed < Duration::from_secs(3));
+
+    let (output, elapsed) = run("timeout");
+    assert_eq!(output.status.code(), Some(43));
+    assert!(elapsed < Duration::from_secs(10));
+
+    let (output, _) = run("exit_by_signal");
+    assert_eq!(output.status.signal(), Some(2));
+
+    // Hooks registered after a Handler was dropped register a new one.
+    let (output, _) = run("hooks_after_drop");
+    assert_eq!(output.status.code(), Some(44));
+
+    let (output, _) = run("forward_after_drop");
+    assert_eq!(output.status.code(), Some(45));
+
+    // Children in our process group got Ctrl-C from the terminal already.
+    let (output, _) = run("forward_terminal");
+    assert_eq!(output.status.code(), Some(47));
+
+    // Subscribers registered before a Handler was dropped keep the thread running.
+    let (output, _) = run("token_after_drop");
+    assert_eq!(output.status.code(), Some(46));
+}
+
+#[cfg(windows)]
+fn test_exit() {}
+
+fn test_builder() {
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let handler = ctrlc::Builder::new()
+        .signals(&[ctrlc::SignalType::Ctrlc])
+        .thread_name("custom-name")
+        .stack_size(256 * 1024)
+        .sa_restart(false)
+        .install_handler(move |signal| {
+            let name = ::std::thread::current().name().map(String::from);
+            tx.send((signal, name)).unwrap();
+        })
+        .unwrap();
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    let (signal, name) = rx
+        .recv_timeout(::std::time::Duration::from_secs(10))
+        .unwrap();
+    assert_eq!(signal, ctrlc::SignalType::Ctrlc);
+    assert_eq!(name.as_deref(), Some("custom-name"));
+
+    drop(handler);
+}
+
+fn test_panic() {
+    let (hook_tx, hook_rx) = ::std::sync::mpsc::channel();
+    ctrlc::set_panic_hook(move |signal, payload| {
+        let message = payload.downcast_ref::<&str>().map(|s| s.to_string());
+        let _ = hook_tx.send((signal, message));
+    });
+
+    // Keep the panic from reaching the hook of the test binary, which cleans up.
+    let std_hook = ::std::panic::take_hook();
+    ::std::panic::set_hook(Box::new(|_| {}));
+
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let mut panicked = false;
+    let handler = ctrlc::Handler::install(move || {
+        if !panicked {
+            panicked = true;
+            panic!("handler panic");
+        }
+        tx.send(()).unwrap();
+    })
+    .unwrap();
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+    let (signal, message) = hook_rx
+        .recv_timeout(::std::time::Duration::from_secs(10))
+        .unwrap();
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+    let handled = rx.recv_timeout(::std::time::Duration::from_secs(10));
+
+    drop(handler);
+    ::std::panic::set_hook(std_hook);
+
+    assert_eq!(signal, ctrlc::SignalType::Ctrlc);
+    assert_eq!(message.as_deref(), Some("handler panic"));
+    handled.unwrap();
+}
+
+#[cfg(unix)]
+fn test_spawn_error() {
+    match ctrlc::Builder::new()
+        .stack_size(usize::MAX)
+        .install_handler(|_| {})
+    {
+        Err(ctrlc::Error::ThreadSpawn(_)) => {}
+        ret => panic!("{:?}", ret),
+    }
+
+    // The failed attempt is rolled back.
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let handler = ctrlc::Handler::install(move || tx.send(()).unwrap()).unwrap();
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+    rx.recv_timeout(::std::time::Duration::from_secs(10))
+        .unwrap();
+    drop(handler);
+}
+
+#[cfg(windows)]
+fn test_spawn_error() {}
+
+// Starts the signal handling thread for the rest of the tests, test_set_handler() then sets
+// the handler on top of it.
+fn test_subscribe_first() {
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let subscriber_tx = tx.clone();
+    // The subscriber outlives the receiver.
+    ctrlc::subscribe(move |_| {
+        let _ = subscriber_tx.send("subscriber");
+    })
+    .unwrap();
+
+    // The running thread has a different name. The policy is set back, the signals below
+    // would end the process otherwise.
+    let builder = ctrlc::Builder::new()
+        .thread_name("other")
+        .policy(ctrlc::Policy::ExitBySignal);
+    match builder.install_handler(|_| {}) {
+        Err(ctrlc::Error::MultipleHandlers) => {}
+        ret => panic!("{:?}", ret),
+    }
+
+    let handler = ctrlc::Handler::install(move || tx.send("handler").unwrap()).unwrap();
+    match ctrlc::Handler::install(|| {}) {
+        Err(ctrlc::Error::MultipleHandlers) => {}
+        ret => panic!("{:?}", ret),
+    }
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    let timeout = ::std::time::Duration::from_secs(10);
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), "handler");
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), "subscriber");
+
+    // Only the closure is removed, the subscriber still gets the signals.
+    drop(handler);
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), "subscriber");
+}
+
 fn test_set_handler() {
     let (tx, rx) = ::std::sync::mpsc::channel();
     ctrlc::set_handler(move || {
@@ -261,6 +1140,13 @@ macro_rules! run_tests {
 }
 
 fn main() {
+    #[cfg(unix)]
+    {
+        if let Ok(scenario) = ::std::env::var(child::SCENARIO) {
+            child::run(&scenario);
+        }
+    }
+
     unsafe {
         platform::setup().unwrap();
     }
@@ -273,7 +1159,28 @@ fn main() {
         (default)(info);
     }));
 
-    run_tests!(test_set_handler);
+    run_tests!(
+        test_handler_drop,
+        test_no_such_signal,
+        test_replace_handler,
+        test_subscribe,
+        test_channel,
+        test_shutdown_token,
+        test_shutdown_hooks,
+        test_defer,
+        test_ignore,
+        test_forward,
+        test_install_mode,
+        test_hangup,
+        test_signal_info,
+        test_stats,
+        test_exit,
+        test_builder,
+        test_panic,
+        test_spawn_error,
+        test_subscribe_first,
+        test_set_handler
+    );
 
     unsafe {
         platform::cleanup().unwrap();
@@ -0,0 +1,241 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+// Polling registers the OS signal handler for the rest of the program, so it gets a process
+// of its own instead of being part of src/tests.rs.
+
+#[cfg(all(unix, feature = "async-std"))]
+extern crate async_io;
+extern crate ctrlc;
+#[cfg(all(unix, feature = "tokio"))]
+extern crate tokio;
+
+#[cfg(unix)]
+mod platform {
+    pub extern crate nix;
+
+    pub unsafe fn raise_ctrl_c() {
+        self::nix::sys::signal::kill(self::nix::unistd::getpid(), self::nix::sys::signal::SIGINT)
+            .unwrap();
+    }
+
+    /// Raise Ctrl-C from another thread after a while, so that the future is pending first.
+    #[allow(dead_code)]
+    pub fn raise_ctrl_c_later() -> ::std::thread::JoinHandle<()> {
+        ::std::thread::spawn(|| {
+            ::std::thread::sleep(::std::time::Duration::from_millis(100));
+            unsafe { raise_ctrl_c() }
+        })
+    }
+}
+
+#[cfg(unix)]
+fn test_try_recv() {
+    ctrlc::init_polling().unwrap();
+    assert_eq!(ctrlc::try_recv().unwrap(), None);
+    assert_eq!(ctrlc::pending_count(), 0);
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    assert_eq!(ctrlc::pending_count(), 1);
+    assert_eq!(ctrlc::try_recv().unwrap(), Some(ctrlc::SignalType::Ctrlc));
+    assert_eq!(ctrlc::try_recv().unwrap(), None);
+    assert_eq!(ctrlc::pending_count(), 0);
+
+    match ctrlc::set_handler(|| {}) {
+        Err(ctrlc::Error::MultipleHandlers) => {}
+        ret => panic!("{:?}", ret),
+    }
+}
+
+#[cfg(unix)]
+fn test_notifier() {
+    use platform::nix::poll::{poll, PollFd, PollFlags};
+    use std::os::unix::io::AsRawFd;
+
+    let mut notifier = ctrlc::Notifier::new().unwrap();
+    assert!(notifier.drain().unwrap().is_empty());
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    let mut fds = [PollFd::new(notifier.as_raw_fd(), PollFlags::POLLIN)];
+    assert_eq!(poll(&mut fds, 10_000).unwrap(), 1);
+    assert_eq!(notifier.drain().unwrap(), vec![ctrlc::SignalType::Ctrlc]);
+    assert_eq!(poll(&mut fds, 0).unwrap(), 0);
+}
+
+#[cfg(unix)]
+fn test_ignore() {
+    let guard = ctrlc::Ignore::new(&[ctrlc::SignalType::Ctrlc]).unwrap();
+
+    unsafe {
+        platform::raise_ctrl_c();
+    }
+
+    assert_eq!(ctrlc::try_recv().unwrap(), None);
+    drop(guard);
+}
+
+#[cfg(all(unix, feature = "tokio"))]
+fn test_tokio() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .unwrap();
+
+    let raised = platform::raise_ctrl_c_later();
+    let signal = runtime.block_on(ctrlc::tokio::ctrl_c()).unwrap();
+    assert_eq!(signal, ctrlc::SignalType::Ctrlc);
+    raised.join().unwrap();
+
+    // Every worker thread of the runtime may get the signal.
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_io()
+        .build()
+        .unwrap();
+
+    let raised = platform::raise_ctrl_c_later();
+    let signal = runtime
+        .block_on(runtime.spawn(ctrlc::tokio::ctrl_c()))
+        .unwrap()
+        .unwrap();
+    assert_eq!(signal, ctrlc::SignalType::Ctrlc);
+    raised.join().unwrap();
+}
+
+#[cfg(not(all(unix, feature = "tokio")))]
+fn test_tokio() {}
+
+#[cfg(all(unix, feature = "async-std"))]
+fn test_async_std() {
+    let raised = platform::raise_ctrl_c_later();
+    let signal = async_io::block_on(ctrlc::async_std::ctrl_c()).unwrap();
+    assert_eq!(signal, ctrlc::SignalType::Ctrlc);
+    raised.join().unwrap();
+}
+
+#[cfg(not(all(unix, feature = "async-std")))]
+fn test_async_std() {}
+
+// Signals that don't fit into the self-pipe must not get lost. The signalfd backend merges
+// pending signals of the same kind instead.
+#[cfg(all(
+    unix,
+    not(all(feature = "signalfd", any(target_os = "linux", target_os = "android")))
+))]
+fn test_overflow() {
+    // Far more than the pipe can hold.
+    const SIGNALS: usize = 100_000;
+
+    let before = ctrlc::stats();
+    // The writes to the full pipe fail, without changing errno for the interrupted code.
+    platform::nix::errno::Errno::clear();
+    for _ in 0..SIGNALS {
+        unsafe {
+            platform::raise_ctrl_c();
+        }
+    }
+    assert_eq!(platform::nix::errno::errno(), 0);
+    assert_eq!(ctrlc::pending_count(), SIGNALS);
+
+    let mut received = 0;
+    while let Some(signal) = ctrlc::try_recv().unwrap() {
+        assert_eq!(signal, ctrlc::SignalType::Ctrlc);
+        received += 1;
+    }
+    assert_eq!(received, SIGNALS);
+
+    let after = ctrlc::stats();
+    assert_eq!(after.received - before.received, SIGNALS as u64);
+    assert_eq!(after.delivered - before.delivered, SIGNALS as u64);
+    assert!(after.overflowed > before.overflowed);
+    assert_eq!(after.dropped, before.dropped);
+}
+
+#[cfg(not(all(
+    unix,
+    not(all(feature = "signalfd", any(target_os = "linux", target_os = "android")))
+)))]
+fn test_overflow() {}
+
+// Signals received after the pipe overflowed are delivered after the ones before them.
+#[cfg(all(
+    unix,
+    feature = "termination",
+    not(all(feature = "signalfd", any(target_os = "linux", target_os = "android")))
+))]
+fn test_overflow_order() {
+    use platform::nix::sys::signal::{kill, SIGTERM};
+    use platform::nix::unistd::getpid;
+
+    const SIGNALS: usize = 100_000;
+
+    let before = ctrlc::stats();
+    for _ in 0..SIGNALS {
+        unsafe {
+            platform::raise_ctrl_c();
+        }
+    }
+    assert!(ctrlc::stats().overflowed > before.overflowed);
+    kill(getpid(), SIGTERM).unwrap();
+
+    let mut received = Vec::with_capacity(SIGNALS + 1);
+    while let Some(signal) = ctrlc::try_recv().unwrap() {
+        received.push(signal);
+    }
+    assert_eq!(received.len(), SIGNALS + 1);
+    assert_eq!(received.pop(), Some(ctrlc::SignalType::Termination));
+    assert!(received.iter().all(|&s| s == ctrlc::SignalType::Ctrlc));
+}
+
+#[cfg(not(all(
+    unix,
+    feature = "termination",
+    not(all(feature = "signalfd", any(target_os = "linux", target_os = "android")))
+)))]
+fn test_overflow_order() {}
+
+// Raising Ctrl-C on Windows needs the console juggling of src/tests.rs.
+#[cfg(windows)]
+fn test_try_recv() {}
+
+#[cfg(windows)]
+fn test_notifier() {}
+
+#[cfg(windows)]
+fn test_ignore() {}
+
+macro_rules! run_tests {
+    ( $($test_fn:ident),* ) => {
+        println!();
+        $(
+            print!("test tests_polling::{} ... ", stringify!($test_fn));
+            $test_fn();
+            println!("ok");
+        )*
+        println!();
+    }
+}
+
+fn main() {
+    run_tests!(
+        test_try_recv,
+        test_notifier,
+        test_ignore,
+        test_tokio,
+        test_async_std,
+        test_overflow,
+        test_overflow_order
+    );
+}
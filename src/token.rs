@@ -0,0 +1,187 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use error::Error;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::time::{Duration, Instant};
+use subscriber::{self, SubscriptionId};
+
+struct Inner {
+    cancelled: Mutex<bool>,
+    condvar: Condvar,
+    children: Mutex<Vec<Weak<Inner>>>,
+    // The subscriber that cancels a token made by ShutdownToken::new(), removed once it is no
+    // longer needed.
+    subscription: Mutex<Option<SubscriptionId>>,
+}
+
+impl Inner {
+    fn new(cancelled: bool) -> Arc<Inner> {
+        Arc::new(Inner {
+            cancelled: Mutex::new(cancelled),
+            condvar: Condvar::new(),
+            children: Mutex::new(Vec::new()),
+            subscription: Mutex::new(None),
+        })
+    }
+
+    fn cancel(&self) {
+        // Children are cancelled first and under the lock, so that a token is never seen
+        // cancelled while its children are not.
+        let mut cancelled = self.cancelled.lock().unwrap();
+        if *cancelled {
+            return;
+        }
+
+        let children = self.children.lock().unwrap().split_off(0);
+        for child in children.iter().filter_map(Weak::upgrade) {
+            child.cancel();
+        }
+
+        // Once cancelled, the token has no use for further signals. Removed before anyone
+        // sees the token cancelled.
+        self.unsubscribe();
+
+        *cancelled = true;
+        drop(cancelled);
+        self.condvar.notify_all();
+    }
+
+    fn unsubscribe(&self) {
+        if let Some(id) = self.subscription.lock().unwrap().take() {
+            subscriber::unsubscribe(id);
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.unsubscribe();
+    }
+}
+
+/// A token that is cancelled when a Ctrl-C signal is received, to tell worker threads to shut
+/// down.
+///
+/// Clones share their state, cancelling one cancels all of them. Tokens created with
+/// [child_token()](struct.ShutdownToken.html#method.child_token) are cancelled together with
+/// their parent, but can also be cancelled on their own.
+///
+/// The token is cancelled on the dedicated signal handling thread, as a
+/// [subscriber](fn.subscribe.html). The subscriber is removed once the token is cancelled or
+/// the last clone of it is dropped.
+///
+/// # Example
+/// ```no_run
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// let token = ctrlc::ShutdownToken::new().expect("Error setting Ctrl-C handler");
+///
+/// let worker = token.clone();
+/// let handle = thread::spawn(move || {
+///     while !worker.is_cancelled() {
+///         thread::sleep(Duration::from_millis(100));
+///     }
+/// });
+///
+/// println!("Waiting for Ctrl-C...");
+/// token.wait();
+/// handle.join().unwrap();
+/// ```
+#[derive(Clone)]
+pub struct ShutdownToken {
+    inner: Arc<Inner>,
+}
+
+impl ShutdownToken {
+    /// Create a token that is cancelled on the next Ctrl-C signal.
+    ///
+    /// # Errors
+    /// Will return an error if a system error occurred while setting the handler.
+    ///
+    pub fn new() -> Result<ShutdownToken, Error> {
+        let inner = Inner::new(false);
+        let weak = Arc::downgrade(&inner);
+        let id = subscriber::subscribe(
+            0,
+            Box::new(move |_| {
+                if let Some(inner) = weak.upgrade() {
+                    inner.cancel();
+                }
+            }),
+        )?;
+        *inner.subscription.lock().unwrap() = Some(id);
+        // A signal may have cancelled the token before the subscription was stored.
+        if *inner.cancelled.lock().unwrap() {
+            inner.unsubscribe();
+        }
+        Ok(ShutdownToken { inner })
+    }
+
+    /// Create a token that is cancelled when this one is, but that can be cancelled without
+    /// affecting this one.
+    pub fn child_token(&self) -> ShutdownToken {
+        let cancelled = self.inner.cancelled.lock().unwrap();
+        let child = Inner::new(*cancelled);
+        if !*cancelled {
+            let mut children = self.inner.children.lock().unwrap();
+            children.retain(|c| c.strong_count() > 0);
+            children.push(Arc::downgrade(&child));
+        }
+        ShutdownToken { inner: child }
+    }
+
+    /// Cancel the token and its children.
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    /// Whether the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        *self.inner.cancelled.lock().unwrap()
+    }
+
+    /// Block until the token is cancelled.
+    pub fn wait(&self) {
+        let mut cancelled = self.inner.cancelled.lock().unwrap();
+        while !*cancelled {
+            cancelled = self.inner.condvar.wait(cancelled).unwrap();
+        }
+    }
+
+    /// Block until the token is cancelled or `timeout` has passed. Returns whether the token
+    /// was cancelled.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut cancelled = self.inner.cancelled.lock().unwrap();
+        while !*cancelled {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            cancelled = self
+                .inner
+                .condvar
+                .wait_timeout(cancelled, deadline - now)
+                .unwrap()
+                .0;
+        }
+        true
+    }
+}
+
+impl fmt::Debug for ShutdownToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ShutdownToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
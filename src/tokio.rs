@@ -0,0 +1,152 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+//! Ctrl-C handling for [tokio](https://tokio.rs), enabled with the `tokio` feature.
+//!
+//! Instead of starting a dedicated signal handling thread, the signals are read from within
+//! the runtime. On Unix the self-pipe the OS signal handler writes to is registered with the
+//! tokio reactor.
+//!
+//! This registers the OS signal handler for the same signals as
+//! [set_handler()](../fn.set_handler.html) and can't be combined with it. The handler stays
+//! registered for the rest of the program, so signals are not missed between two calls of
+//! [ctrl_c()](fn.ctrl_c.html). If there are several streams, each signal is received by one
+//! of them.
+//!
+//! # Example
+//! ```no_run
+//! # #[cfg(unix)]
+//! # fn main() {
+//! extern crate ctrlc;
+//! extern crate tokio;
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread()
+//!     .enable_io()
+//!     .build()
+//!     .unwrap();
+//!
+//! println!("Waiting for Ctrl-C...");
+//! runtime
+//!     .block_on(ctrlc::tokio::ctrl_c())
+//!     .expect("Error waiting for Ctrl-C");
+//! println!("Got it! Exiting...");
+//! # }
+//! # #[cfg(windows)]
+//! # fn main() {}
+//! ```
+
+#[cfg(unix)]
+extern crate tokio;
+
+use error::Error;
+use handler;
+#[cfg(unix)]
+use platform;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use stream::{self, PollRecv, Stream};
+use SignalType;
+
+#[cfg(unix)]
+use self::tokio::io::unix::AsyncFd;
+#[cfg(unix)]
+use std::os::unix::io::{BorrowedFd, OwnedFd};
+
+/// Future returned by [ctrl_c()](fn.ctrl_c.html).
+pub type CtrlC = stream::CtrlC<SignalStream>;
+
+/// Stream of received signals, returned by [stream()](fn.stream.html).
+#[derive(Debug)]
+pub struct SignalStream {
+    #[cfg(unix)]
+    fd: AsyncFd<OwnedFd>,
+}
+
+/// Wait for the next Ctrl-C signal.
+///
+/// The returned future must be polled from within a tokio runtime with IO enabled.
+///
+/// # Errors
+/// Resolves with an error if another `ctrlc::set_handler()` handler exists or if a
+/// system error occurred while setting the handler or waiting for the signal.
+///
+pub fn ctrl_c() -> CtrlC {
+    CtrlC::default()
+}
+
+/// Create a stream that yields every received Ctrl-C signal.
+///
+/// Must be called from within a tokio runtime with IO enabled.
+///
+/// # Errors
+/// Will return an error if another `ctrlc::set_handler()` handler exists or if a
+/// system error occurred while setting the handler.
+///
+pub fn stream() -> Result<SignalStream, Error> {
+    SignalStream::new()
+}
+
+impl SignalStream {
+    /// Poll for the next received signal.
+    pub fn poll_recv(&mut self, cx: &mut Context) -> Poll<Result<SignalType, Error>> {
+        PollRecv::poll_recv(self, cx)
+    }
+}
+
+impl PollRecv for SignalStream {
+    #[cfg(unix)]
+    fn new() -> Result<SignalStream, Error> {
+        handler::install_threadless()?;
+
+        // Every stream gets its own descriptor, because a descriptor can only be registered
+        // with the reactor once.
+        let fd = unsafe { BorrowedFd::borrow_raw(platform::read_fd()) }
+            .try_clone_to_owned()
+            .map_err(Error::System)?;
+        Ok(SignalStream {
+            fd: unsafe { AsyncFd::register(fd) }.map_err(|e| Error::System(e.into_parts().1))?,
+        })
+    }
+
+    #[cfg(windows)]
+    fn new() -> Result<SignalStream, Error> {
+        handler::install_threadless()?;
+        Ok(SignalStream {})
+    }
+
+    #[cfg(unix)]
+    fn poll_recv(&mut self, cx: &mut Context) -> Poll<Result<SignalType, Error>> {
+        loop {
+            let mut guard = match self.fd.poll_read_ready(cx) {
+                Poll::Ready(Ok(guard)) => guard,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(Error::System(e))),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            match unsafe { platform::poll_ctrl_c() } {
+                Ok(Some(info)) => return Poll::Ready(Ok(info.signal)),
+                Ok(None) => guard.clear_ready(),
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    fn poll_recv(&mut self, cx: &mut Context) -> Poll<Result<SignalType, Error>> {
+        stream::forwarded::poll_recv(cx)
+    }
+}
+
+impl Stream for SignalStream {
+    type Item = Result<SignalType, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.poll_recv(cx).map(Some)
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright (c) 2017 CtrlC developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use policy;
+use std::process;
+use std::thread;
+use std::time::Duration;
+use SignalType;
+
+/// What happens when the process is still running after the shutdown deadline passed.
+///
+/// See [set_handler_with_timeout()](fn.set_handler_with_timeout.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutAction {
+    /// Exit the process with the given code.
+    Exit(i32),
+    /// Raise the signal again with its default disposition, so that the process is killed by
+    /// it. On Windows the process exits with `STATUS_CONTROL_C_EXIT`.
+    Reraise,
+}
+
+/// Start a thread that applies `action` once `timeout` has passed.
+pub fn start(timeout: Duration, action: TimeoutAction, signal: SignalType) {
+    // Without a watchdog we can still try to shut down gracefully.
+    let _ = thread::Builder::new()
+        .name("ctrl-c-watchdog".into())
+        .spawn(move || {
+            thread::sleep(timeout);
+            match action {
+                TimeoutAction::Exit(code) => process::exit(code),
+                TimeoutAction::Reraise => policy::exit_by_signal(signal),
+            }
+        });
+}